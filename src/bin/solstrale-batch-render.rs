@@ -9,7 +9,10 @@ use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use solstrale::ray_trace;
 use solstrale::renderer::RenderImageStrategy::OnlyFinal;
 use solstrale::util::wgpu_util::buffer_to_image;
-use solstrale_desktop_rust::model::{Creator, CreatorContext, parse_scene_yaml};
+use solstrale_desktop_rust::model::scene_override::{PathSegment, SceneOverride, TemplateVariable};
+use solstrale_desktop_rust::model::{
+    Creator, CreatorContext, RenderMode, TemplateContext, parse_scene_yaml_with_overrides,
+};
 
 #[derive(Parser)]
 #[command(version, about, long_about = None, disable_help_flag = true)]
@@ -29,16 +32,50 @@ struct Cli {
     #[arg(short, long, default_value_t = 100, value_parser = clap::value_parser!(u16).range(1..))]
     num_frames: u16,
 
+    /// Frame rate used to calculate the `time` template variable
+    #[arg(long, default_value_t = 30., value_parser = parse_fps)]
+    fps: f64,
+
+    /// Name of a scene variant from the `variants` section to render
+    #[arg(long)]
+    variant: Option<String>,
+
+    /// Name of a camera from the `cameras` section to render with
+    #[arg(long, conflicts_with = "all_cameras")]
+    camera: Option<String>,
+
+    /// Render the frames once for each camera in the `cameras` section, to files
+    /// prefixed with the camera name
+    #[arg(long)]
+    all_cameras: bool,
+
+    /// Override a value in the scene, for example `--set camera.vertical_fov_degrees=40`
+    /// or `--set world[2].sphere.radius=3`. Can be given multiple times
+    #[arg(long = "set", value_name = "PATH=VALUE")]
+    overrides: Vec<SceneOverride>,
+
+    /// Define a variable available to the scene templates, alongside `frameIndex`.
+    /// Can be given multiple times
+    #[arg(long = "var", value_name = "NAME=VALUE")]
+    variables: Vec<TemplateVariable>,
+
     #[clap(long, action = clap::ArgAction::HelpLong)]
     help: Option<bool>,
 }
 
+fn parse_fps(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(fps) if fps > 0. => Ok(fps),
+        _ => Err(format!("'{s}' is not a positive number")),
+    }
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
     let cli = Cli::parse();
     let num_frames = cli.num_frames as usize;
     let screen_width = cli.width as usize;
     let screen_height = cli.height as usize;
-    let scene_path = cli.scene_path;
+    let scene_path = &cli.scene_path;
 
     let (device, queue) = pollster::block_on(async {
         let instance = eframe::wgpu::Instance::default();
@@ -76,68 +113,117 @@ fn main() -> Result<(), Box<dyn Error>> {
             .unwrap()
             .progress_chars("##-");
 
-    let total_progress_bar =
-        multi_progress.add(ProgressBar::new(num_frames as u64).with_style(total_progress_style));
-
-    for frame_index in 0..num_frames {
-        let scene_yaml = fs::read_to_string(scene_path.clone())?;
-
-        let mut scene = parse_scene_yaml(&scene_yaml, frame_index)?.create(&CreatorContext {
-            screen_width,
-            screen_height,
-            device: &device,
-            queue: &queue,
-        })?;
-        scene.render_config.render_image_strategy = OnlyFinal;
-
-        let samples_per_pixel = scene.render_config.samples_per_pixel as u64;
-        total_progress_bar.set_length(num_frames as u64 * samples_per_pixel);
-        let frame_progress_bar = multi_progress
-            .add(ProgressBar::new(samples_per_pixel).with_style(frame_progress_style.clone()));
-
-        let (output_sender, output_receiver) = channel();
-        let (_, camera_config_receiver) = channel();
-        let (_, abort_receiver) = channel();
-
-        let device_clone = device.clone();
-        let queue_clone = queue.clone();
-        thread::spawn(move || {
-            ray_trace(
-                scene,
-                &output_sender,
-                &abort_receiver,
-                &camera_config_receiver,
-                &device_clone,
-                &queue_clone,
-                false,
-            )
-            .unwrap();
-        });
-
-        let mut image_buffer: Option<wgpu::Buffer> = None;
-        for render_output in &output_receiver {
-            image_buffer = Some(render_output.output_buffer);
-            total_progress_bar.inc(1);
-            frame_progress_bar.inc(1);
+    // The scene's width_height is resolved against the screen size when it is parsed
+    let mut template_ctx = TemplateContext {
+        frame_index: 0,
+        frame_count: num_frames,
+        fps: cli.fps,
+        width: screen_width,
+        height: screen_height,
+        mode: RenderMode::Batch,
+        variables: cli.variables.clone(),
+    };
+
+    // Each camera is rendered to files prefixed with its name, `None` being the scene camera
+    let cameras: Vec<Option<String>> = if cli.all_cameras {
+        let scene = parse_scene_yaml_with_overrides(
+            &fs::read_to_string(scene_path)?,
+            &template_ctx,
+            &cli.overrides,
+            cli.variant.as_deref(),
+        )?;
+        if scene.cameras.is_empty() {
+            return Err("The scene has no cameras to render".into());
         }
+        scene.cameras.into_keys().map(Some).collect()
+    } else {
+        vec![cli.camera.clone()]
+    };
+    let total_frames = (num_frames * cameras.len()) as u64;
 
-        if let Some(buffer) = image_buffer {
-            let image = buffer_to_image(
-                &device,
-                &queue,
-                &buffer,
-                screen_width as u32,
-                screen_height as u32,
-            );
-
-            image.save(format!("frame_{:0>8}.png", frame_index))?;
+    let total_progress_bar =
+        multi_progress.add(ProgressBar::new(total_frames).with_style(total_progress_style));
+
+    for camera in &cameras {
+        let mut overrides = cli.overrides.clone();
+        if let Some(name) = camera {
+            overrides.push(SceneOverride {
+                path: vec![PathSegment::Key("active_camera".to_string())],
+                value: name.as_str().into(),
+            });
         }
-
-        for _ in output_receiver {
-            total_progress_bar.inc(1);
-            frame_progress_bar.inc(1);
+        let file_prefix = match camera {
+            Some(name) if cli.all_cameras => format!("{name}_"),
+            _ => String::new(),
+        };
+
+        for frame_index in 0..num_frames {
+            let scene_yaml = fs::read_to_string(scene_path)?;
+
+            template_ctx.frame_index = frame_index;
+            let mut scene = parse_scene_yaml_with_overrides(
+                &scene_yaml,
+                &template_ctx,
+                &overrides,
+                cli.variant.as_deref(),
+            )?
+            .create(&CreatorContext {
+                screen_width,
+                screen_height,
+                device: &device,
+                queue: &queue,
+            })?;
+            scene.render_config.render_image_strategy = OnlyFinal;
+
+            let samples_per_pixel = scene.render_config.samples_per_pixel as u64;
+            total_progress_bar.set_length(total_frames * samples_per_pixel);
+            let frame_progress_bar = multi_progress
+                .add(ProgressBar::new(samples_per_pixel).with_style(frame_progress_style.clone()));
+
+            let (output_sender, output_receiver) = channel();
+            let (_, camera_config_receiver) = channel();
+            let (_, abort_receiver) = channel();
+
+            let device_clone = device.clone();
+            let queue_clone = queue.clone();
+            thread::spawn(move || {
+                ray_trace(
+                    scene,
+                    &output_sender,
+                    &abort_receiver,
+                    &camera_config_receiver,
+                    &device_clone,
+                    &queue_clone,
+                    false,
+                )
+                .unwrap();
+            });
+
+            let mut image_buffer: Option<wgpu::Buffer> = None;
+            for render_output in &output_receiver {
+                image_buffer = Some(render_output.output_buffer);
+                total_progress_bar.inc(1);
+                frame_progress_bar.inc(1);
+            }
+
+            if let Some(buffer) = image_buffer {
+                let image = buffer_to_image(
+                    &device,
+                    &queue,
+                    &buffer,
+                    screen_width as u32,
+                    screen_height as u32,
+                );
+
+                image.save(format!("{file_prefix}frame_{:0>8}.png", frame_index))?;
+            }
+
+            for _ in output_receiver {
+                total_progress_bar.inc(1);
+                frame_progress_bar.inc(1);
+            }
+            multi_progress.remove(&frame_progress_bar);
         }
-        multi_progress.remove(&frame_progress_bar);
     }
 
     multi_progress.clear().unwrap();
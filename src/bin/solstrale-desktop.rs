@@ -13,6 +13,8 @@ use hhmmss::Hhmmss;
 use once_cell::sync::Lazy;
 use std::sync::Arc;
 
+use solstrale_desktop_rust::camera_panel::CameraSettings;
+use solstrale_desktop_rust::image_statistics::StatisticsPanel;
 use solstrale_desktop_rust::keyboard::{is_ctrl_space, is_enter};
 use solstrale_desktop_rust::model::scene::Scene;
 use solstrale_desktop_rust::model::{
@@ -21,8 +23,10 @@ use solstrale_desktop_rust::model::{
 use solstrale_desktop_rust::render_output::render_output;
 use solstrale_desktop_rust::yaml_editor::{create_layouter, yaml_editor};
 use solstrale_desktop_rust::{
-    DEFAULT_SCENE, ErrorInfo, RenderControl, RenderedImage, help, load_scene, loading_output,
-    render_button, reset_confirm, save_image, save_scene, yaml_editor,
+    DEFAULT_SCENE, ErrorInfo, RenderControl, RenderedImage, camera_bookmarks, camera_menu,
+    camera_panel, camera_recorder, gizmo, help, image_statistics, image_view, load_scene,
+    loading_output, render_button, render_controls, reset_confirm, save_image, save_scene,
+    snapshots, variant_picker, yaml_editor,
 };
 
 static ROOT_DOCUMENTATION_STRUCTURE: Lazy<DocumentationStructure> =
@@ -60,6 +64,10 @@ struct SolstraleApp {
     error_info: ErrorInfo,
     dialogs: Dialogs,
     display_help: bool,
+    display_camera_panel: bool,
+    display_statistics: bool,
+    display_snapshots: bool,
+    statistics_panel: StatisticsPanel,
     dark_mode: bool,
 }
 
@@ -88,11 +96,31 @@ impl SolstraleApp {
         let mut dark_mode = dark_light::detect().map_or(None, |m| Some(m == Mode::Dark));
 
         let mut display_help = true;
+        let mut display_camera_panel = false;
+        let mut display_statistics = false;
+        let mut display_snapshots = false;
+        let mut camera_settings = CameraSettings::default();
         if let Some(storage) = ctx.storage {
             if let Some(value) = storage.get_string("display_help") {
                 display_help =
                     bool::from_str(&value).expect("Invalid app configuration for display help");
             }
+            if let Some(value) = storage.get_string("display_camera_panel") {
+                display_camera_panel = bool::from_str(&value)
+                    .expect("Invalid app configuration for display camera panel");
+            }
+            if let Some(value) = storage.get_string("display_statistics") {
+                display_statistics = bool::from_str(&value)
+                    .expect("Invalid app configuration for display statistics");
+            }
+            if let Some(value) = storage.get_string("display_snapshots") {
+                display_snapshots = bool::from_str(&value)
+                    .expect("Invalid app configuration for display snapshots");
+            }
+            if let Some(value) = storage.get_string("camera_settings") {
+                camera_settings = serde_yaml::from_str(&value)
+                    .expect("Invalid app configuration for camera settings");
+            }
             if let Some(value) = storage.get_string("dark_mode") {
                 dark_mode =
                     Some(bool::from_str(&value).expect("Invalid app configuration for dark mode"));
@@ -121,8 +149,15 @@ impl SolstraleApp {
         SolstraleApp {
             scene_yaml: yaml,
             display_help,
+            display_camera_panel,
+            display_statistics,
+            display_snapshots,
             dark_mode: dark_mode.unwrap_or(false),
             rendered_image,
+            render_control: RenderControl {
+                camera_settings,
+                ..Default::default()
+            },
             ..Default::default()
         }
     }
@@ -163,6 +198,16 @@ impl App for SolstraleApp {
                     }
                 });
 
+                image_view::show_menu(ui, &mut self.render_control, &mut self.rendered_image);
+
+                camera_menu::show(
+                    ui,
+                    &mut self.render_control,
+                    &self.rendered_image,
+                    &mut self.scene_yaml,
+                    &mut self.error_info,
+                );
+
                 let reset_button = ui.button("Reset");
                 if reset_button.clicked() {
                     self.dialogs.show_reset_confirm_dialog = true;
@@ -183,8 +228,24 @@ impl App for SolstraleApp {
                     );
                 }
 
+                variant_picker::show(ui, &mut self.render_control);
+
+                camera_bookmarks::show(ui, &mut self.render_control);
+
+                gizmo::show_mode(ui, &mut self.render_control);
+
+                camera_recorder::show(
+                    ui,
+                    &mut self.render_control,
+                    &mut self.scene_yaml,
+                    &mut self.error_info,
+                );
+
                 ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
                     ui.checkbox(&mut self.display_help, "Display help");
+                    ui.checkbox(&mut self.display_camera_panel, "Camera panel");
+                    ui.checkbox(&mut self.display_statistics, "Statistics");
+                    ui.checkbox(&mut self.display_snapshots, "Snapshots");
                     if ui.checkbox(&mut self.dark_mode, "Dark mode").changed() {
                         ctx.set_visuals(if self.dark_mode {
                             Visuals::dark()
@@ -232,21 +293,38 @@ impl App for SolstraleApp {
                     ..Margin::default()
                 })
                 .show(ui, |ui| {
-                    ui.add(
-                        ProgressBar::new(self.rendered_image.progress as f32).text(format!(
-                            "{:.0}% {} {:.1}FPS {:.1}MPPS",
-                            self.rendered_image.progress * 100.,
-                            self.rendered_image.estimated_time_left.hhmmss(),
-                            self.rendered_image.fps,
-                            self.rendered_image.fps
-                                * self.rendered_image.width as f64
-                                * self.rendered_image.height as f64
-                                / 1_000_000.,
-                        )),
-                    )
+                    ui.horizontal(|ui| {
+                        render_controls::show(ui, &mut self.render_control, &self.rendered_image);
+                        ui.add(
+                            ProgressBar::new(self.rendered_image.progress as f32).text(format!(
+                                "{} {} {:.1}FPS {:.1}MPPS",
+                                render_controls::progress_text(
+                                    &self.render_control,
+                                    &self.rendered_image,
+                                    ctx.input(|i| i.time),
+                                ),
+                                self.rendered_image.estimated_time_left.hhmmss(),
+                                self.rendered_image.fps,
+                                self.rendered_image.fps
+                                    * self.rendered_image.width as f64
+                                    * self.rendered_image.height as f64
+                                    / 1_000_000.,
+                            )),
+                        )
+                    })
                 });
         });
 
+        TopBottomPanel::bottom("snapshot-panel").show_animated(ctx, self.display_snapshots, |ui| {
+            snapshots::show_strip(
+                ui,
+                &mut self.render_control,
+                &self.rendered_image,
+                &mut self.scene_yaml,
+                &mut self.error_info,
+            )
+        });
+
         let documentation_structure = get_documentation_structure_by_yaml_path(
             &ROOT_DOCUMENTATION_STRUCTURE,
             &yaml_editor::get_yaml_path(&self.scene_yaml, ctx),
@@ -257,14 +335,16 @@ impl App for SolstraleApp {
                 .inner_margin(Margin::same(0))
                 .show(ui, |ui| {
                     ScrollArea::both().min_scrolled_width(300.).show(ui, |ui| {
-                        ui.add(yaml_editor(
+                        let output = yaml_editor(
                             &mut self.scene_yaml,
                             &mut create_layouter(),
                             Vec2 {
                                 x: 300.0,
                                 y: ui.available_height(),
                             },
-                        ));
+                        )
+                        .show(ui);
+                        yaml_editor::scroll_to_moved_cursor(ui, &output);
 
                         if is_ctrl_space(ui)
                             && let Some(doc) = &documentation_structure
@@ -288,6 +368,19 @@ impl App for SolstraleApp {
                 })
             });
 
+        Window::new("Camera")
+            .open(&mut self.display_camera_panel)
+            .default_width(250.)
+            .resizable(false)
+            .show(ctx, |ui| camera_panel::show(ui, &mut self.render_control));
+
+        Window::new("Statistics")
+            .open(&mut self.display_statistics)
+            .resizable(false)
+            .show(ctx, |ui| {
+                image_statistics::show(ui, &mut self.statistics_panel, &self.rendered_image)
+            });
+
         CentralPanel::default()
             .frame(egui::Frame {
                 inner_margin: Margin::same(0),
@@ -312,6 +405,7 @@ impl App for SolstraleApp {
 
                 if (self.render_control.loading_scene || self.render_control.render_requested)
                     && !self.render_control.camera_updated
+                    && !self.render_control.scene_updated
                 {
                     loading_output::show(ui);
                 }
@@ -321,7 +415,7 @@ impl App for SolstraleApp {
                     &mut self.render_control,
                     &mut self.rendered_image,
                     &mut self.error_info,
-                    &self.scene_yaml,
+                    &mut self.scene_yaml,
                     available_size,
                 );
             });
@@ -337,6 +431,15 @@ impl App for SolstraleApp {
 
     fn save(&mut self, storage: &mut dyn Storage) {
         storage.set_string("display_help", self.display_help.to_string());
+        storage.set_string(
+            "display_camera_panel",
+            self.display_camera_panel.to_string(),
+        );
+        storage.set_string("display_statistics", self.display_statistics.to_string());
+        storage.set_string("display_snapshots", self.display_snapshots.to_string());
+        if let Ok(camera_settings) = serde_yaml::to_string(&self.render_control.camera_settings) {
+            storage.set_string("camera_settings", camera_settings);
+        }
         storage.set_string("dark_mode", self.dark_mode.to_string());
         storage.set_string("scene_yaml", self.scene_yaml.to_owned())
     }
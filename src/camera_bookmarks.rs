@@ -0,0 +1,92 @@
+use std::error::Error;
+
+use eframe::egui::{ComboBox, Ui};
+
+use crate::RenderControl;
+use crate::camera_yaml::{add_map_entry, format_number, format_vec};
+use crate::model::camera_config::CameraConfig;
+use crate::model::orbit_camera::OrbitCamera;
+
+pub fn show(ui: &mut Ui, render_control: &mut RenderControl) {
+    let cameras: Vec<String> = render_control
+        .scene
+        .as_ref()
+        .map(|s| s.cameras.keys().cloned().collect())
+        .unwrap_or_default();
+
+    // The selection is kept visible even if the scene failed to parse, so that it can be cleared
+    if cameras.is_empty() && render_control.camera.is_none() {
+        return;
+    }
+
+    let mut selected = render_control.camera.clone();
+    ComboBox::from_id_salt("camera-picker")
+        .selected_text(selected.as_deref().unwrap_or("Scene camera"))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut selected, None, "Scene camera");
+            for c in cameras {
+                ui.selectable_value(&mut selected, Some(c.clone()), c);
+            }
+        })
+        .response
+        .on_hover_text("Select which of the camera bookmarks to view the scene from");
+
+    if selected != render_control.camera {
+        render_control.camera = selected;
+        render_control.render_requested = true;
+    }
+}
+
+/// Roll of the camera for the scene, left out when the camera is not rolled
+pub fn roll_degrees(orbit_camera: &OrbitCamera) -> Option<f64> {
+    let roll = orbit_camera.roll_degrees();
+    (format_number(roll) != "0").then_some(roll)
+}
+
+/// Adds the current view as a new camera to the `cameras` of the scene
+pub fn save_view_as_bookmark(
+    render_control: &mut RenderControl,
+    scene_yaml: &mut String,
+) -> Result<(), Box<dyn Error>> {
+    let (Some(orbit_camera), Some(scene)) =
+        (&render_control.orbit_camera, &mut render_control.scene)
+    else {
+        return Ok(());
+    };
+
+    let name = (1..)
+        .map(|i| format!("view_{i}"))
+        .find(|name| !scene.cameras.contains_key(name))
+        .unwrap_or_default();
+
+    let roll = roll_degrees(orbit_camera);
+    let mut camera = format!(
+        "vertical_fov_degrees: {}\naperture_size: {}\nlook_from: {}\nlook_at: {}\nup: {}",
+        format_number(orbit_camera.vertical_fov_degrees),
+        format_number(orbit_camera.aperture_size),
+        format_vec(orbit_camera.look_from()),
+        format_vec(orbit_camera.look_at()),
+        format_vec(orbit_camera.up),
+    );
+    if let Some(roll) = roll {
+        camera += &format!("\nroll_degrees: {}", format_number(roll));
+    }
+    *scene_yaml = add_map_entry(scene_yaml, "cameras", &name, &camera)?;
+
+    // Makes the bookmark selectable right away, without waiting for the scene to be parsed again
+    scene.cameras.insert(
+        name,
+        CameraConfig {
+            vertical_fov_degrees: Some(orbit_camera.vertical_fov_degrees),
+            aperture_size: Some(orbit_camera.aperture_size),
+            focus_distance: None,
+            lens: None,
+            look_from: orbit_camera.look_from().into(),
+            look_at: Some(orbit_camera.look_at().into()),
+            up: Some(orbit_camera.up.into()),
+            roll_degrees: roll,
+            path: None,
+        },
+    );
+    Ok(())
+}
@@ -0,0 +1,253 @@
+use std::error::Error;
+
+use eframe::egui::UiKind::Menu;
+use eframe::egui::{Button, Ui};
+use solstrale::geo::Aabb;
+
+use crate::camera_bookmarks::{roll_degrees, save_view_as_bookmark};
+use crate::camera_yaml::{
+    format_number, format_vec, update_camera_fields, update_map_entry_fields,
+};
+use crate::keyboard::is_ctrl_b;
+use crate::model::orbit_camera::OrbitCamera;
+use crate::model::ray_cast::BoundingBox;
+use crate::model::{CreatorContext, TemplateContext, parse_scene_yaml_with_overrides};
+use crate::{CameraMode, ErrorInfo, RenderControl, RenderedImage};
+
+pub fn show(
+    ui: &mut Ui,
+    render_control: &mut RenderControl,
+    rendered_image: &RenderedImage,
+    scene_yaml: &mut String,
+    error_info: &mut ErrorInfo,
+) {
+    ui.menu_button("Camera", |ui| {
+        let previous_mode = render_control.camera_mode;
+        ui.radio_value(&mut render_control.camera_mode, CameraMode::Orbit, "Orbit")
+            .on_hover_text("Drag to orbit, right drag to pan and scroll to zoom");
+        ui.radio_value(&mut render_control.camera_mode, CameraMode::Fly, "Fly")
+            .on_hover_text(
+                "Drag to look around, WASD to move, Q and E to move up and down, shift to move faster and scroll to change the speed",
+            );
+        if render_control.camera_mode != previous_mode {
+            render_control.fly_camera = None;
+        }
+        ui.separator();
+
+        if ui
+            .add_enabled(
+                render_control.orbit_camera.is_some(),
+                Button::new("Apply view to scene"),
+            )
+            .on_hover_text(
+                "Write the current camera view into the camera of the scene, or into the selected camera bookmark. Replaces any camera animation",
+            )
+            .clicked()
+        {
+            ui.close_kind(Menu);
+            if let Err(err) = apply_view_to_scene(render_control, scene_yaml) {
+                error_info.handle(err);
+            }
+        }
+
+        if ui
+            .add_enabled(
+                render_control.orbit_camera.is_some(),
+                Button::new("Save view as bookmark").shortcut_text("Ctrl+B"),
+            )
+            .on_hover_text("Add the current camera view to the camera bookmarks of the scene")
+            .clicked()
+        {
+            ui.close_kind(Menu);
+            if let Err(err) = save_view_as_bookmark(render_control, scene_yaml) {
+                error_info.handle(err);
+            }
+        }
+
+        let aspect_ratio = rendered_image.width as f64 / rendered_image.height.max(1) as f64;
+        if ui
+            .add_enabled(
+                render_control.orbit_camera.is_some(),
+                Button::new("Frame all"),
+            )
+            .on_hover_text("Move the camera so the whole scene is in view")
+            .clicked()
+        {
+            ui.close_kind(Menu);
+            if let Err(err) = frame_all(render_control, aspect_ratio) {
+                error_info.handle(err);
+            }
+        }
+
+        if ui
+            .add_enabled(
+                render_control.orbit_camera.is_some() && render_control.selected_object.is_some(),
+                Button::new("Frame selected"),
+            )
+            .on_hover_text(
+                "Move the camera so the selected object is in view. Double click on an object to frame it",
+            )
+            .clicked()
+        {
+            ui.close_kind(Menu);
+            if let Err(err) = frame_selected(render_control, aspect_ratio) {
+                error_info.handle(err);
+            }
+        }
+
+        if ui
+            .add_enabled(
+                render_control.orbit_camera.is_some(),
+                Button::new("Reset view"),
+            )
+            .on_hover_text(
+                "Move the camera back to the view defined in the scene, or in the selected camera bookmark",
+            )
+            .clicked()
+        {
+            ui.close_kind(Menu);
+            if let Err(err) = reset_view(render_control, rendered_image, scene_yaml) {
+                error_info.handle(err);
+            }
+        }
+    });
+
+    if is_ctrl_b(ui)
+        && let Err(err) = save_view_as_bookmark(render_control, scene_yaml)
+    {
+        error_info.handle(err);
+    }
+}
+
+pub fn apply_view_to_scene(
+    render_control: &RenderControl,
+    scene_yaml: &mut String,
+) -> Result<(), Box<dyn Error>> {
+    let Some(orbit_camera) = &render_control.orbit_camera else {
+        return Ok(());
+    };
+
+    let mut fields = vec![
+        ("look_from", Some(format_vec(orbit_camera.look_from()))),
+        ("look_at", Some(format_vec(orbit_camera.look_at()))),
+        ("up", Some(format_vec(orbit_camera.up))),
+        (
+            "roll_degrees",
+            roll_degrees(orbit_camera).map(format_number),
+        ),
+        ("focus_distance", None),
+        ("path", None),
+    ];
+    // The field of view comes from the lens when the camera has one
+    if render_control
+        .scene
+        .as_ref()
+        .is_none_or(|s| s.camera.lens.is_none())
+    {
+        fields.insert(
+            0,
+            (
+                "vertical_fov_degrees",
+                Some(format_number(orbit_camera.vertical_fov_degrees)),
+            ),
+        );
+    }
+
+    *scene_yaml = update_viewed_camera_fields(render_control, scene_yaml, &fields)?;
+    Ok(())
+}
+
+/// Sets or removes fields of the camera the scene is viewed from, which is the selected camera
+/// bookmark if any, and otherwise the `camera` of the scene
+fn update_viewed_camera_fields(
+    render_control: &RenderControl,
+    scene_yaml: &str,
+    fields: &[(&str, Option<String>)],
+) -> Result<String, Box<dyn Error>> {
+    match &render_control.camera {
+        Some(name) => update_map_entry_fields(scene_yaml, "cameras", name, fields),
+        None => update_camera_fields(scene_yaml, fields),
+    }
+}
+
+pub fn frame_all(
+    render_control: &mut RenderControl,
+    aspect_ratio: f64,
+) -> Result<(), Box<dyn Error>> {
+    let Some(scene) = &render_control.scene else {
+        return Ok(());
+    };
+    let bounds = scene.bounding_box()?;
+    frame(render_control, &bounds, aspect_ratio);
+    Ok(())
+}
+
+pub fn frame_selected(
+    render_control: &mut RenderControl,
+    aspect_ratio: f64,
+) -> Result<(), Box<dyn Error>> {
+    let (Some(scene), Some(index)) = (&render_control.scene, render_control.selected_object) else {
+        return Ok(());
+    };
+    let Some(hittable) = scene.world.get(index) else {
+        return Ok(());
+    };
+    let bounds = hittable.bounding_box()?;
+    frame(render_control, &bounds, aspect_ratio);
+    Ok(())
+}
+
+fn frame(render_control: &mut RenderControl, bounds: &Aabb, aspect_ratio: f64) {
+    let Some(orbit_camera) = &mut render_control.orbit_camera else {
+        return;
+    };
+    orbit_camera.frame(bounds, aspect_ratio);
+    // The fly camera takes over the view every frame, so it is moved there right away
+    if render_control.camera_mode == CameraMode::Fly {
+        orbit_camera.set_view(orbit_camera.target_look_from(), orbit_camera.target_target);
+        render_control.fly_camera = None;
+    }
+    render_control.camera_updated = true;
+}
+
+/// Moves the camera back to the view written in the scene yaml for the camera the scene is
+/// viewed from, which is the selected camera bookmark if any. The scene overrides select it
+pub fn reset_view(
+    render_control: &mut RenderControl,
+    rendered_image: &RenderedImage,
+    scene_yaml: &str,
+) -> Result<(), Box<dyn Error>> {
+    let Some(resources) = &rendered_image.render_resources else {
+        return Ok(());
+    };
+    let width = render_control.render_size.x as usize;
+    let height = render_control.render_size.y as usize;
+
+    let scene = parse_scene_yaml_with_overrides(
+        scene_yaml,
+        &TemplateContext {
+            width,
+            height,
+            ..Default::default()
+        },
+        &render_control.scene_overrides(),
+        render_control.variant.as_deref(),
+    )?;
+
+    let ctx = CreatorContext {
+        screen_width: width,
+        screen_height: height,
+        device: &resources.device,
+        queue: &resources.queue,
+    };
+    let mut orbit_camera = OrbitCamera::new(
+        &scene.camera,
+        &ctx,
+        render_control.camera_settings.damping_factor,
+    )?;
+    orbit_camera.set_scene_bounds(&scene.bounding_box()?);
+    render_control.orbit_camera = Some(orbit_camera);
+    render_control.fly_camera = None;
+    render_control.camera_updated = true;
+    Ok(())
+}
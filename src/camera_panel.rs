@@ -0,0 +1,225 @@
+use eframe::egui::{CollapsingHeader, DragValue, Grid, Ui};
+use serde::{Deserialize, Serialize};
+use solstrale::geo::vec3::Vec3;
+
+use crate::model::orbit_camera::View;
+use crate::{CameraMode, RenderControl};
+
+/// How the camera responds to the mouse and keyboard, adjustable in the camera panel
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CameraSettings {
+    /// Fraction of the remaining way the camera moves each frame, 1 moves right away
+    pub damping_factor: f64,
+    /// Radians orbited per point dragged
+    pub orbit_sensitivity: f64,
+    /// Fraction of the scene size panned per point dragged
+    pub pan_sensitivity: f64,
+    /// Fraction of the scene size zoomed per point scrolled
+    pub zoom_sensitivity: f64,
+    /// Degrees orbited for each press of an arrow key
+    pub key_step_degrees: f64,
+    /// Fraction of the viewport resolution rendered while the camera moves, 1 always renders
+    /// at full resolution
+    pub interaction_resolution: f64,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        CameraSettings {
+            damping_factor: 1.,
+            orbit_sensitivity: 0.01,
+            pan_sensitivity: 0.001,
+            zoom_sensitivity: 0.001,
+            key_step_degrees: 15.,
+            interaction_resolution: 0.5,
+        }
+    }
+}
+
+/// Shows the values of the orbit camera for editing, the buttons for snapping to the sides of
+/// the target, and the camera settings
+pub fn show(ui: &mut Ui, render_control: &mut RenderControl) {
+    if let Some(orbit_camera) = &mut render_control.orbit_camera {
+        let mut target = orbit_camera.target_target;
+        let mut distance = orbit_camera.target_distance;
+        let mut azimuth = orbit_camera.target_azimuth.to_degrees();
+        let mut polar = orbit_camera.target_polar.to_degrees();
+        let mut roll = orbit_camera.target_roll.to_degrees();
+        let mut changed = false;
+
+        Grid::new("camera-values").num_columns(2).show(ui, |ui| {
+            ui.label("Target");
+            ui.horizontal(|ui| {
+                for value in [&mut target.x, &mut target.y, &mut target.z] {
+                    changed |= ui.add(DragValue::new(value).speed(0.01)).changed();
+                }
+            });
+            ui.end_row();
+
+            ui.label("Distance");
+            changed |= ui
+                .add(
+                    DragValue::new(&mut distance)
+                        .speed(0.01)
+                        .range(0.001..=f64::MAX),
+                )
+                .changed();
+            ui.end_row();
+
+            ui.label("Azimuth");
+            changed |= ui
+                .add(DragValue::new(&mut azimuth).speed(0.5).suffix("°"))
+                .changed();
+            ui.end_row();
+
+            ui.label("Polar");
+            changed |= ui
+                .add(
+                    DragValue::new(&mut polar)
+                        .speed(0.5)
+                        .range(0.5..=179.5)
+                        .suffix("°"),
+                )
+                .changed();
+            ui.end_row();
+
+            ui.label("Roll")
+                .on_hover_text("Tilt of the camera, also changed with the [ and ] keys");
+            let rolled = ui
+                .add(DragValue::new(&mut roll).speed(0.5).suffix("°"))
+                .changed();
+            if rolled {
+                orbit_camera.set_roll(roll.to_radians());
+            }
+            changed |= rolled;
+            ui.end_row();
+
+            ui.label("Field of view");
+            changed |= ui
+                .add(
+                    DragValue::new(&mut orbit_camera.vertical_fov_degrees)
+                        .speed(0.2)
+                        .range(1.0..=179.0)
+                        .suffix("°"),
+                )
+                .changed();
+            ui.end_row();
+
+            ui.label("Aperture");
+            changed |= ui
+                .add(
+                    DragValue::new(&mut orbit_camera.aperture_size)
+                        .speed(0.01)
+                        .range(0.0..=f64::MAX),
+                )
+                .changed();
+            ui.end_row();
+        });
+
+        let mut snapped = false;
+        ui.horizontal_wrapped(|ui| {
+            for view in View::ALL {
+                if ui.button(format!("{view:?}")).clicked() {
+                    orbit_camera.snap_to(view);
+                    snapped = true;
+                }
+            }
+        });
+
+        if changed {
+            orbit_camera.set_orbit(
+                Vec3::new(target.x, target.y, target.z),
+                distance,
+                azimuth.to_radians(),
+                polar.to_radians(),
+            );
+        }
+        // The fly camera takes over the view every frame, so there is no time to animate
+        if snapped && render_control.camera_mode == CameraMode::Fly {
+            orbit_camera.set_orbit(
+                orbit_camera.target_target,
+                orbit_camera.target_distance,
+                orbit_camera.target_azimuth,
+                orbit_camera.target_polar,
+            );
+        }
+        if changed || snapped {
+            render_control.fly_camera = None;
+            render_control.camera_updated = true;
+        }
+    } else {
+        ui.label("No scene is loaded");
+    }
+
+    CollapsingHeader::new("Settings").show(ui, |ui| {
+        let settings = &mut render_control.camera_settings;
+        Grid::new("camera-settings").num_columns(2).show(ui, |ui| {
+            ui.label("Damping").on_hover_text(
+                "How much of the way the camera moves each frame, 1 moves right away",
+            );
+            if ui
+                .add(
+                    DragValue::new(&mut settings.damping_factor)
+                        .speed(0.01)
+                        .range(0.01..=1.0),
+                )
+                .changed()
+                && let Some(orbit_camera) = &mut render_control.orbit_camera
+            {
+                orbit_camera.damping_factor = settings.damping_factor;
+            }
+            ui.end_row();
+
+            ui.label("Orbit sensitivity");
+            ui.add(
+                DragValue::new(&mut settings.orbit_sensitivity)
+                    .speed(0.0001)
+                    .range(0.0001..=1.0),
+            );
+            ui.end_row();
+
+            ui.label("Pan sensitivity");
+            ui.add(
+                DragValue::new(&mut settings.pan_sensitivity)
+                    .speed(0.0001)
+                    .range(0.0001..=1.0),
+            );
+            ui.end_row();
+
+            ui.label("Zoom sensitivity");
+            ui.add(
+                DragValue::new(&mut settings.zoom_sensitivity)
+                    .speed(0.0001)
+                    .range(0.0001..=1.0),
+            );
+            ui.end_row();
+
+            ui.label("Key step")
+                .on_hover_text("Degrees orbited for each press of an arrow key");
+            ui.add(
+                DragValue::new(&mut settings.key_step_degrees)
+                    .speed(0.5)
+                    .range(1.0..=90.0)
+                    .suffix("°"),
+            );
+            ui.end_row();
+
+            ui.label("Interaction resolution").on_hover_text(
+                "Fraction of the resolution rendered while the camera moves, which keeps large viewports responsive",
+            );
+            ui.add(
+                DragValue::new(&mut settings.interaction_resolution)
+                    .speed(0.01)
+                    .range(0.05..=1.0),
+            );
+            ui.end_row();
+        });
+        if ui.button("Restore defaults").clicked() {
+            *settings = CameraSettings::default();
+            if let Some(orbit_camera) = &mut render_control.orbit_camera {
+                orbit_camera.damping_factor = settings.damping_factor;
+            }
+        }
+    });
+}
@@ -0,0 +1,146 @@
+use std::error::Error;
+use std::mem;
+use std::time::Duration;
+
+use eframe::egui::{DragValue, Ui};
+use solstrale::geo::vec3::Vec3;
+
+use crate::camera_yaml::{format_vec, update_camera_fields};
+use crate::model::orbit_camera::OrbitCamera;
+use crate::{ErrorInfo, RenderControl};
+
+/// Records the orbit camera position over time, so that camera movements made with the mouse
+/// can be written to the scene as keyframes and batch rendered
+pub struct CameraRecorder {
+    pub fps: f64,
+    start_time: Option<f64>,
+    samples: Vec<(Vec3, Vec3)>,
+}
+
+impl Default for CameraRecorder {
+    fn default() -> Self {
+        CameraRecorder {
+            fps: 30.,
+            start_time: None,
+            samples: Vec::new(),
+        }
+    }
+}
+
+impl CameraRecorder {
+    pub fn is_recording(&self) -> bool {
+        self.start_time.is_some()
+    }
+
+    pub fn start(&mut self, time: f64) {
+        self.start_time = Some(time);
+        self.samples.clear();
+    }
+
+    /// Samples the camera once for every frame that has passed since the previous sample.
+    /// Returns the time until the next frame is due, or `None` when not recording
+    pub fn sample(&mut self, time: f64, orbit_camera: &OrbitCamera) -> Option<Duration> {
+        let start_time = self.start_time?;
+        let frame_time = |frame: usize| start_time + frame as f64 / self.fps;
+
+        while frame_time(self.samples.len()) <= time {
+            self.samples
+                .push((orbit_camera.look_from(), orbit_camera.look_at()));
+        }
+        Some(Duration::from_secs_f64(
+            frame_time(self.samples.len()) - time,
+        ))
+    }
+
+    /// Stops recording and writes the recorded camera movement as keyframes into the scene
+    pub fn stop(&mut self, scene_yaml: &mut String) -> Result<(), Box<dyn Error>> {
+        self.start_time = None;
+        let samples = mem::take(&mut self.samples);
+        if samples.is_empty() {
+            return Ok(());
+        }
+
+        *scene_yaml = update_camera_fields(
+            scene_yaml,
+            &[
+                (
+                    "look_from",
+                    Some(keyframes_yaml(samples.iter().map(|s| s.0))),
+                ),
+                ("look_at", Some(keyframes_yaml(samples.iter().map(|s| s.1)))),
+                ("path", None),
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+/// Keyframes for every frame, except where the camera is standing still. Those keyframes are
+/// left out since interpolating between the surrounding ones gives the same value
+fn keyframes_yaml(values: impl Iterator<Item = Vec3>) -> String {
+    let values: Vec<String> = values.map(format_vec).collect();
+    let mut yaml = "keyframes:".to_string();
+
+    for (frame, value) in values.iter().enumerate() {
+        let same_as_previous = frame > 0 && values[frame - 1] == *value;
+        let same_as_next = values.get(frame + 1).is_some_and(|v| v == value);
+        if !(same_as_previous && same_as_next) {
+            yaml += &format!("\n- {{ frame: {frame}, value: \"{value}\" }}");
+        }
+    }
+    yaml
+}
+
+pub fn show(
+    ui: &mut Ui,
+    render_control: &mut RenderControl,
+    scene_yaml: &mut String,
+    error_info: &mut ErrorInfo,
+) {
+    let recorder = &mut render_control.camera_recorder;
+    let mut recording = recorder.is_recording();
+
+    if ui
+        .toggle_value(&mut recording, "⏺ Record camera")
+        .on_hover_text(
+            "Record the camera movements and write them to the scene as keyframes when stopped",
+        )
+        .changed()
+    {
+        if recording {
+            recorder.start(ui.input(|i| i.time));
+        } else if let Err(err) = recorder.stop(scene_yaml) {
+            error_info.handle(err);
+        }
+    }
+
+    ui.add_enabled(
+        !recording,
+        DragValue::new(&mut recorder.fps)
+            .range(1..=240)
+            .suffix(" fps"),
+    )
+    .on_hover_text("Frame rate of the recorded camera keyframes");
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn keyframes() {
+        let values = vec![
+            Vec3::new(0., 0., 0.),
+            Vec3::new(0., 0., 0.),
+            Vec3::new(0., 0., 0.),
+            Vec3::new(1., -0.00001, 1.23456),
+        ];
+        assert_eq!(
+            "keyframes:
+- { frame: 0, value: \"0, 0, 0\" }
+- { frame: 2, value: \"0, 0, 0\" }
+- { frame: 3, value: \"1, 0, 1.2346\" }",
+            keyframes_yaml(values.into_iter())
+        );
+    }
+}
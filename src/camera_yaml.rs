@@ -0,0 +1,589 @@
+use std::error::Error;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use solstrale::geo::vec3::Vec3;
+
+static KEY_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new("^(\\s*)([\\w_]+):").unwrap());
+static TEMPLATE_TAG_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new("^\\s*\\{%-?\\s*(\\w+)").unwrap());
+static TEMPLATE_COMMENT_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new("^\\s*\\{#.*#}\\s*$").unwrap());
+
+/// Sets or removes fields of the top level `camera` block in the scene yaml, leaving all
+/// other text, including Tera template tags, untouched. A value of `None` removes the field.
+/// Values spanning multiple lines are indented below the key
+pub fn update_camera_fields(
+    yaml: &str,
+    fields: &[(&str, Option<String>)],
+) -> Result<String, Box<dyn Error>> {
+    update_block_fields(yaml, fields, |lines| {
+        let start = find_top_level_key(lines, "camera")
+            .ok_or("Could not find a 'camera:' block in the scene")?;
+        Ok((start, block_end(lines, start, |l| indentation(l) > 0)))
+    })
+}
+
+/// Same as [update_camera_fields] for the fields of an item in a top level list, for example
+/// the object at `index` in `world`. `item_count` is the number of items in the parsed list,
+/// see [list_item_line]
+pub fn update_list_item_fields(
+    yaml: &str,
+    list_key: &str,
+    index: usize,
+    item_count: usize,
+    fields: &[(&str, Option<String>)],
+) -> Result<String, Box<dyn Error>> {
+    let start = find_list_item(yaml, list_key, index, item_count)?;
+    update_block_fields(yaml, fields, |lines| Ok(list_item_block(lines, start)))
+}
+
+/// Checks that the fields of an item in a top level list hold plain values, which
+/// [update_list_item_fields] can replace without losing template expressions or keyframes
+pub fn check_list_item_fields(
+    yaml: &str,
+    list_key: &str,
+    index: usize,
+    item_count: usize,
+    keys: &[&str],
+) -> Result<(), Box<dyn Error>> {
+    let start = find_list_item(yaml, list_key, index, item_count)?;
+    let lines: Vec<String> = yaml.lines().map(|l| l.to_string()).collect();
+    let (start, end) = list_item_block(&lines, start);
+
+    for key in keys {
+        if let Some((key_start, key_end)) = field_block(&lines, start, end, key)
+            && lines[key_start..key_end]
+                .iter()
+                .any(|l| l.contains("{{") || l.contains("{%") || l.contains("keyframes:"))
+        {
+            return Err(format!(
+                "The '{key}' of item {index} of '{list_key}' is generated by a template or animated, and would be overwritten"
+            )
+            .into());
+        }
+    }
+    Ok(())
+}
+
+fn find_list_item(
+    yaml: &str,
+    list_key: &str,
+    index: usize,
+    item_count: usize,
+) -> Result<usize, Box<dyn Error>> {
+    list_item_line(yaml, list_key, index, item_count).ok_or_else(|| {
+        format!(
+            "Could not find item {index} of '{list_key}' in the scene, it may be generated by a template"
+        )
+        .into()
+    })
+}
+
+/// Line of the item and the index after its last line
+fn list_item_block(lines: &[String], start: usize) -> (usize, usize) {
+    let item_indent = indentation(&lines[start]);
+    (
+        start,
+        block_end(lines, start, |l| indentation(l) > item_indent),
+    )
+}
+
+/// Same as [update_camera_fields] for the fields of a named entry in a top level map, for
+/// example a camera in `cameras`
+pub fn update_map_entry_fields(
+    yaml: &str,
+    map_key: &str,
+    name: &str,
+    fields: &[(&str, Option<String>)],
+) -> Result<String, Box<dyn Error>> {
+    update_block_fields(yaml, fields, |lines| {
+        let start = map_entry_line(lines, map_key, name).ok_or_else(|| {
+            format!("Could not find a '{name}:' block in '{map_key}' of the scene")
+        })?;
+        let entry_indent = indentation(&lines[start]);
+        Ok((
+            start,
+            block_end(lines, start, |l| indentation(l) > entry_indent),
+        ))
+    })
+}
+
+/// Sets or removes fields of the block found by `find_block`, given as the line of the key
+/// of the block and the index after its last line
+fn update_block_fields(
+    yaml: &str,
+    fields: &[(&str, Option<String>)],
+    find_block: impl Fn(&[String]) -> Result<(usize, usize), Box<dyn Error>>,
+) -> Result<String, Box<dyn Error>> {
+    let mut lines: Vec<String> = yaml.lines().map(|l| l.to_string()).collect();
+
+    for (key, value) in fields {
+        let (start, end) = find_block(&lines)?;
+        let child_indent = child_indentation(&lines[start + 1..end]);
+
+        let field_lines: Vec<String> = match value {
+            None => vec![],
+            Some(v) => field_lines(key, v, child_indent),
+        };
+
+        match field_block(&lines, start, end, key) {
+            Some((key_start, key_end)) => {
+                lines.splice(key_start..key_end, field_lines);
+            }
+            None => {
+                lines.splice(end..end, field_lines);
+            }
+        }
+    }
+
+    let mut result = lines.join("\n");
+    if yaml.ends_with('\n') {
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+/// Line of a field in the block from `start` to `end`, and the index after its last line
+fn field_block(lines: &[String], start: usize, end: usize, key: &str) -> Option<(usize, usize)> {
+    let child_indent = child_indentation(&lines[start + 1..end]);
+    let key_start = (start + 1..end).find(|i| {
+        KEY_REGEX
+            .captures(&lines[*i])
+            .is_some_and(|c| c[1].len() == child_indent && &c[2] == key)
+    })?;
+    let key_end = block_end(lines, key_start, |l| {
+        indentation(l) > child_indent
+            || (indentation(l) == child_indent && l.trim_start().starts_with('-'))
+    });
+    Some((key_start, key_end))
+}
+
+/// Adds a named entry to the end of a top level map in the scene yaml, for example a camera
+/// to the `cameras` map. The map is created at the end of the yaml if it does not exist
+pub fn add_map_entry(
+    yaml: &str,
+    map_key: &str,
+    name: &str,
+    value: &str,
+) -> Result<String, Box<dyn Error>> {
+    let mut lines: Vec<String> = yaml.lines().map(|l| l.to_string()).collect();
+
+    match find_top_level_key(&lines, map_key) {
+        Some(start) => {
+            let end = block_end(&lines, start, |l| indentation(l) > 0);
+            let child_indent = child_indentation(&lines[start + 1..end]);
+            lines.splice(end..end, field_lines(name, value, child_indent));
+        }
+        None => {
+            lines.push(format!("{map_key}:"));
+            lines.append(&mut field_lines(name, value, 2));
+        }
+    }
+
+    let mut result = lines.join("\n");
+    if yaml.ends_with('\n') {
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+/// Line index of an item in a top level list of the scene yaml, for example the objects in
+/// `world`. The index is into the parsed list of `item_count` items. Items generated by
+/// template tags can not be told apart from the written ones, so nothing is found when the
+/// list contains template tags or when its written items are not the parsed ones
+pub fn list_item_line(
+    yaml: &str,
+    list_key: &str,
+    index: usize,
+    item_count: usize,
+) -> Option<usize> {
+    let lines: Vec<String> = yaml.lines().map(|l| l.to_string()).collect();
+    let start = find_top_level_key(&lines, list_key)?;
+    let end = block_end(&lines, start, |l| {
+        indentation(l) > 0 || l.trim_start().starts_with('-')
+    });
+    if lines[start + 1..end].iter().any(|l| l.contains("{%")) {
+        return None;
+    }
+
+    let items: Vec<usize> = (start + 1..end)
+        .filter(|i| {
+            let l = lines[*i].trim_start();
+            l == "-" || l.starts_with("- ")
+        })
+        .collect();
+    let item_indent = items.iter().map(|i| indentation(&lines[*i])).min()?;
+    let items: Vec<usize> = items
+        .into_iter()
+        .filter(|i| indentation(&lines[*i]) == item_indent)
+        .collect();
+    if items.len() != item_count {
+        return None;
+    }
+    items.get(index).copied()
+}
+
+/// Line index of a named entry in a top level map, when its fields are written on the lines
+/// below it
+fn map_entry_line(lines: &[String], map_key: &str, name: &str) -> Option<usize> {
+    let start = find_top_level_key(lines, map_key)?;
+    let end = block_end(lines, start, |l| indentation(l) > 0);
+    let entry_indent = child_indentation(&lines[start + 1..end]);
+    (start + 1..end).find(|i| {
+        KEY_REGEX.captures(&lines[*i]).is_some_and(|c| {
+            let rest = lines[*i][c[0].len()..].trim();
+            c[1].len() == entry_indent
+                && &c[2] == name
+                && (rest.is_empty() || rest.starts_with('#'))
+        })
+    })
+}
+
+fn find_top_level_key(lines: &[String], key: &str) -> Option<usize> {
+    lines.iter().position(|l| {
+        l.strip_prefix(key)
+            .and_then(|rest| rest.strip_prefix(':'))
+            .is_some_and(|rest| rest.trim().is_empty() || rest.trim().starts_with('#'))
+    })
+}
+
+/// Indentation of the keys in a block, from the first key found. Defaults to 2 spaces
+fn child_indentation(block: &[String]) -> usize {
+    block
+        .iter()
+        .filter(|l| !TEMPLATE_TAG_REGEX.is_match(l))
+        .find_map(|l| KEY_REGEX.captures(l).map(|c| c[1].len()))
+        .unwrap_or(2)
+}
+
+/// Values spanning multiple lines are put below the key, with one more level of indentation
+fn field_lines(key: &str, value: &str, indent: usize) -> Vec<String> {
+    if !value.contains('\n') {
+        return vec![format!("{}{key}: {value}", " ".repeat(indent))];
+    }
+
+    std::iter::once(format!("{}{key}:", " ".repeat(indent)))
+        .chain(
+            value
+                .lines()
+                .map(|l| format!("{}{l}", " ".repeat(indent + 2))),
+        )
+        .collect()
+}
+
+/// Index after the last line of the block starting at the line `start`, where the block
+/// continues as long as lines are content. Template tags are only included when they are
+/// balanced within the block, so that conditionals and loops surrounding it stay intact
+fn block_end(lines: &[String], start: usize, is_content: impl Fn(&str) -> bool) -> usize {
+    let mut end = start + 1;
+    let mut depth = 0;
+
+    for (i, l) in lines.iter().enumerate().skip(start + 1) {
+        if l.trim().is_empty() || TEMPLATE_COMMENT_REGEX.is_match(l) {
+            continue;
+        }
+        if let Some(c) = TEMPLATE_TAG_REGEX.captures(l) {
+            match &c[1] {
+                t if t.starts_with("end") => depth -= 1,
+                "if" | "for" | "macro" | "block" | "filter" | "raw" => depth += 1,
+                _ => {}
+            }
+            if depth < 0 {
+                break;
+            }
+            if depth == 0 {
+                end = i + 1;
+            }
+            continue;
+        }
+        if !is_content(l) {
+            break;
+        }
+        if depth == 0 {
+            end = i + 1;
+        }
+    }
+    end
+}
+
+/// Formats a vector as a scene position, rounded to keep the yaml readable
+pub fn format_vec(v: Vec3) -> String {
+    format!(
+        "{}, {}, {}",
+        format_number(v.x),
+        format_number(v.y),
+        format_number(v.z)
+    )
+}
+
+pub fn format_number(v: f64) -> String {
+    // Adding zero turns -0 into 0
+    ((v * 10000.).round() / 10000. + 0.).to_string()
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn update_fields() {
+        let yaml = "camera:
+  vertical_fov_degrees: 35.0
+  look_from: 278, 278, -800
+  look_at:
+    keyframes:
+    - frame: 0
+      value: 1, 2, 3
+{% if mode == \"batch\" %}
+  aperture_size: 1
+{% endif %}
+world:
+  - sphere:
+      center: 0, 0, 0
+";
+
+        let updated = update_camera_fields(
+            yaml,
+            &[
+                ("look_from", Some("1, 2, 3".to_string())),
+                ("look_at", Some("4, 5, 6".to_string())),
+                ("up", Some("0, 0, 1".to_string())),
+                ("vertical_fov_degrees", None),
+                ("path", Some("end_frame: 10\nlook_from: {}".to_string())),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(
+            "camera:
+  look_from: 1, 2, 3
+  look_at: 4, 5, 6
+{% if mode == \"batch\" %}
+  aperture_size: 1
+{% endif %}
+  up: 0, 0, 1
+  path:
+    end_frame: 10
+    look_from: {}
+world:
+  - sphere:
+      center: 0, 0, 0
+",
+            updated
+        );
+    }
+
+    #[test]
+    fn add_entry() {
+        let yaml = "camera:
+  look_from: 0, 0, 0
+cameras:
+    front:
+      look_from: 0, 0, -1
+world: []
+";
+        assert_eq!(
+            "camera:
+  look_from: 0, 0, 0
+cameras:
+    front:
+      look_from: 0, 0, -1
+    view_1:
+      look_from: 1, 2, 3
+      look_at: 0, 0, 0
+world: []
+",
+            add_map_entry(
+                yaml,
+                "cameras",
+                "view_1",
+                "look_from: 1, 2, 3\nlook_at: 0, 0, 0"
+            )
+            .unwrap()
+        );
+
+        assert_eq!(
+            "world: []
+cameras:
+  view_1:
+    look_from: 1, 2, 3",
+            add_map_entry("world: []", "cameras", "view_1", "look_from: 1, 2, 3\n").unwrap()
+        );
+    }
+
+    #[test]
+    fn update_map_entry() {
+        let yaml = "camera:
+  look_from: 0, 0, 0
+cameras:
+  front:
+    look_from: 0, 0, -1
+    up: 0, 1, 0
+  side:
+    look_from: 1, 0, 0
+world: []
+";
+        assert_eq!(
+            "camera:
+  look_from: 0, 0, 0
+cameras:
+  front:
+    look_from: 0, 0, -1
+    up: 0, 1, 0
+  side:
+    look_from: 1, 2, 3
+    look_at: 0, 0, 0
+world: []
+",
+            update_map_entry_fields(
+                yaml,
+                "cameras",
+                "side",
+                &[
+                    ("look_from", Some("1, 2, 3".to_string())),
+                    ("look_at", Some("0, 0, 0".to_string())),
+                ]
+            )
+            .unwrap()
+        );
+        assert!(update_map_entry_fields(yaml, "cameras", "top", &[("up", None)]).is_err());
+        assert!(update_map_entry_fields(yaml, "camera", "look_from", &[("up", None)]).is_err());
+    }
+
+    #[test]
+    fn update_list_item() {
+        let yaml = "world:
+  - sphere:
+      center: 0, 0, 0
+      radius: 1
+  - quad:
+      q: 0, 0, 0
+      transformations:
+      - translation: 1, 1, 1
+  - box:
+      a: 0, 0, 0
+";
+        assert_eq!(
+            "world:
+  - sphere:
+      center: 0, 0, 0
+      radius: 1
+  - quad:
+      q: 0, 0, 0
+      transformations:
+        - rotation_y: 45
+        - translation: 1, 2, 3
+  - box:
+      a: 0, 0, 0
+",
+            update_list_item_fields(
+                yaml,
+                "world",
+                1,
+                3,
+                &[(
+                    "transformations",
+                    Some("- rotation_y: 45\n- translation: 1, 2, 3".to_string())
+                )]
+            )
+            .unwrap()
+        );
+        assert!(update_list_item_fields(yaml, "world", 3, 3, &[]).is_err());
+        // A variant with another world
+        assert!(update_list_item_fields(yaml, "world", 1, 2, &[]).is_err());
+    }
+
+    #[test]
+    fn check_list_item() {
+        let yaml = "world:
+  - sphere:
+      center: {{ x }}, 0, 0
+      radius: 1
+  - box:
+      a: 0, 0, 0
+      transformations:
+        - translation:
+            keyframes:
+              - frame: 0
+                value: 0, 0, 0
+  - quad:
+      q: 0, 0, 0
+      material:
+        lambertian:
+          albedo:
+            color:
+              keyframes: []
+";
+        assert!(check_list_item_fields(yaml, "world", 0, 3, &["radius"]).is_ok());
+        assert!(check_list_item_fields(yaml, "world", 0, 3, &["center", "radius"]).is_err());
+        assert!(check_list_item_fields(yaml, "world", 1, 3, &["transformations"]).is_err());
+        assert!(check_list_item_fields(yaml, "world", 2, 3, &["transformations"]).is_ok());
+        assert!(check_list_item_fields(yaml, "world", 2, 4, &["transformations"]).is_err());
+    }
+
+    #[test]
+    fn update_templated_list_item() {
+        let yaml = "world:
+{% for x in range(end=2) %}
+  - sphere:
+      center: {{ x }}, 0, 0
+      radius: 1
+{% endfor %}
+  - box:
+      a: 0, 0, 0
+";
+        // The box is the third object of the scene, but the first one written
+        let fields = [(
+            "transformations",
+            Some("- translation: 1, 2, 3".to_string()),
+        )];
+        assert!(update_list_item_fields(yaml, "world", 0, 3, &fields).is_err());
+        assert!(update_list_item_fields(yaml, "world", 2, 3, &fields).is_err());
+    }
+
+    #[test]
+    fn list_item() {
+        let yaml = "camera:
+  look_from: 0, 0, 0
+world:
+- sphere:
+    center: 0, 0, 0
+    material:
+      lambertian:
+        albedo:
+          color: 1, 1, 1
+  # A comment
+- quad:
+    q: 0, 0, 0
+- box:
+    a: 0, 0, 0
+";
+        assert_eq!(Some(3), list_item_line(yaml, "world", 0, 3));
+        assert_eq!(Some(10), list_item_line(yaml, "world", 1, 3));
+        assert_eq!(Some(12), list_item_line(yaml, "world", 2, 3));
+        assert_eq!(None, list_item_line(yaml, "world", 3, 3));
+        assert_eq!(None, list_item_line(yaml, "cameras", 0, 3));
+        // A variant with another world
+        assert_eq!(None, list_item_line(yaml, "world", 0, 4));
+
+        let yaml = "world:
+  {% for x in range(end=2) %}
+  - sphere:
+      center: {{ x }}, 0, 0
+  {% endfor %}
+  - box:
+      a: 0, 0, 0
+";
+        assert_eq!(None, list_item_line(yaml, "world", 0, 3));
+        assert_eq!(None, list_item_line(yaml, "world", 2, 3));
+    }
+
+    #[test]
+    fn missing_camera() {
+        assert!(update_camera_fields("world: []", &[("up", None)]).is_err());
+    }
+}
@@ -0,0 +1,283 @@
+use crate::RenderResources;
+use crate::image_view::read_pixels;
+use eframe::egui::{DragValue, Ui};
+use eframe::wgpu;
+use image::RgbImage;
+
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub enum ToneMapping {
+    /// Values above 1 are clipped
+    #[default]
+    Clamp,
+    Reinhard,
+    /// Filmic curve approximating the ACES reference rendering transform
+    AcesFilmic,
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub enum Channel {
+    #[default]
+    Rgb,
+    Red,
+    Green,
+    Blue,
+    Luminance,
+    /// Luminance as a heatmap from blue for dark to red for bright, and white above the range
+    FalseColor,
+}
+
+/// How the rendered values are turned into colors on screen. Only changes the display, unless
+/// chosen to also be used for saved images
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct DisplaySettings {
+    /// Brightness change in stops, where every stop doubles the brightness
+    pub exposure: f32,
+    pub tone_mapping: ToneMapping,
+    pub gamma: f32,
+    pub channel: Channel,
+    pub apply_to_saved_image: bool,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        DisplaySettings {
+            exposure: 0.,
+            tone_mapping: ToneMapping::default(),
+            gamma: 1.,
+            channel: Channel::default(),
+            apply_to_saved_image: false,
+        }
+    }
+}
+
+/// Range of the false color heatmap, in stops relative to a luminance of 1
+const FALSE_COLOR_MIN_STOPS: f32 = -8.;
+const FALSE_COLOR_MAX_STOPS: f32 = 4.;
+
+/// Colors of the false color heatmap, evenly spread over its range
+const FALSE_COLORS: [[f32; 3]; 6] = [
+    [0., 0., 0.5],
+    [0., 0.5, 1.],
+    [0., 0.8, 0.2],
+    [1., 1., 0.],
+    [1., 0., 0.],
+    [1., 1., 1.],
+];
+
+/// Display transform for the shader in `render_output`, which must match
+/// [`DisplaySettings::apply`]
+pub const DISPLAY_SHADER: &str = r#"
+fn luminance(c: vec3<f32>) -> f32 {
+    return dot(c, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+fn tone_map(c: vec3<f32>) -> vec3<f32> {
+    switch display.tone_mapping {
+        case 1u: {
+            return c / (1.0 + c);
+        }
+        case 2u: {
+            return clamp(
+                (c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14),
+                vec3<f32>(0.0),
+                vec3<f32>(1.0),
+            );
+        }
+        default: {
+            return clamp(c, vec3<f32>(0.0), vec3<f32>(1.0));
+        }
+    }
+}
+
+fn false_color(l: f32) -> vec3<f32> {
+    var colors = array<vec3<f32>, 6>(
+        vec3<f32>(0.0, 0.0, 0.5),
+        vec3<f32>(0.0, 0.5, 1.0),
+        vec3<f32>(0.0, 0.8, 0.2),
+        vec3<f32>(1.0, 1.0, 0.0),
+        vec3<f32>(1.0, 0.0, 0.0),
+        vec3<f32>(1.0, 1.0, 1.0),
+    );
+    let t = clamp((log2(max(l, 1e-10)) + 8.0) / 12.0, 0.0, 1.0) * 5.0;
+    let i = min(u32(t), 4u);
+    return mix(colors[i], colors[i + 1u], t - f32(i));
+}
+
+fn display_color(raw: vec3<f32>) -> vec3<f32> {
+    let c = max(raw, vec3<f32>(0.0)) * exp2(display.exposure);
+    var isolated = c;
+    switch display.channel {
+        case 1u: { isolated = vec3<f32>(c.r); }
+        case 2u: { isolated = vec3<f32>(c.g); }
+        case 3u: { isolated = vec3<f32>(c.b); }
+        case 4u: { isolated = vec3<f32>(luminance(c)); }
+        case 5u: { return false_color(luminance(c)); }
+        default: {}
+    }
+    return pow(tone_map(isolated), vec3<f32>(1.0 / display.gamma));
+}
+"#;
+
+impl DisplaySettings {
+    /// Values for the display uniform of the shader: exposure, tone mapping, gamma and channel
+    pub fn uniform(&self) -> (f32, u32, f32, u32) {
+        (
+            self.exposure,
+            self.tone_mapping as u32,
+            self.gamma,
+            self.channel as u32,
+        )
+    }
+
+    /// Color shown for a rendered value, the same as the display shader
+    pub fn apply(&self, raw: [f32; 3]) -> [f32; 3] {
+        let scale = self.exposure.exp2();
+        let c = raw.map(|v| v.max(0.) * scale);
+        let isolated = match self.channel {
+            Channel::Rgb => c,
+            Channel::Red => [c[0]; 3],
+            Channel::Green => [c[1]; 3],
+            Channel::Blue => [c[2]; 3],
+            Channel::Luminance => [luminance(c); 3],
+            Channel::FalseColor => return false_color(luminance(c)),
+        };
+        isolated.map(|v| self.tone_map(v).powf(1. / self.gamma))
+    }
+
+    fn tone_map(&self, v: f32) -> f32 {
+        match self.tone_mapping {
+            ToneMapping::Clamp => v.clamp(0., 1.),
+            ToneMapping::Reinhard => v / (1. + v),
+            ToneMapping::AcesFilmic => {
+                ((v * (2.51 * v + 0.03)) / (v * (2.43 * v + 0.59) + 0.14)).clamp(0., 1.)
+            }
+        }
+    }
+}
+
+fn luminance(c: [f32; 3]) -> f32 {
+    0.2126 * c[0] + 0.7152 * c[1] + 0.0722 * c[2]
+}
+
+fn false_color(l: f32) -> [f32; 3] {
+    let range = FALSE_COLOR_MAX_STOPS - FALSE_COLOR_MIN_STOPS;
+    let t = ((l.max(1e-10).log2() - FALSE_COLOR_MIN_STOPS) / range).clamp(0., 1.)
+        * (FALSE_COLORS.len() - 1) as f32;
+    let i = (t as usize).min(FALSE_COLORS.len() - 2);
+    let f = t - i as f32;
+    [0, 1, 2].map(|c| FALSE_COLORS[i][c] * (1. - f) + FALSE_COLORS[i + 1][c] * f)
+}
+
+/// Reads the rendered image back from the GPU with the display settings applied, for saving
+/// the image as it is shown
+pub fn buffer_to_display_image(
+    resources: &RenderResources,
+    buffer: &wgpu::Buffer,
+    width: u32,
+    height: u32,
+    settings: &DisplaySettings,
+) -> RgbImage {
+    let pixels = read_pixels(resources, buffer, 0, width as u64 * height as u64);
+    RgbImage::from_fn(width, height, |x, y| {
+        let p = pixels[(y * width + x) as usize];
+        image::Rgb(
+            settings
+                .apply([p[0], p[1], p[2]])
+                .map(|v| (v.clamp(0., 1.) * 255.).round() as u8),
+        )
+    })
+}
+
+pub fn show(ui: &mut Ui, settings: &mut DisplaySettings) {
+    ui.horizontal(|ui| {
+        ui.label("Exposure");
+        ui.add(
+            DragValue::new(&mut settings.exposure)
+                .speed(0.05)
+                .range(-16.0..=16.0)
+                .suffix(" stops"),
+        );
+    });
+    ui.horizontal(|ui| {
+        ui.label("Gamma");
+        ui.add(
+            DragValue::new(&mut settings.gamma)
+                .speed(0.01)
+                .range(0.2..=5.0),
+        )
+        .on_hover_text("Saved images use a gamma of 2 unless the display settings are used");
+    });
+    ui.separator();
+
+    ui.label("Tone mapping");
+    let tone_mapping = &mut settings.tone_mapping;
+    ui.radio_value(tone_mapping, ToneMapping::Clamp, "Clamp");
+    ui.radio_value(tone_mapping, ToneMapping::Reinhard, "Reinhard");
+    ui.radio_value(tone_mapping, ToneMapping::AcesFilmic, "ACES filmic");
+    ui.separator();
+
+    ui.label("Channel");
+    let channel = &mut settings.channel;
+    ui.radio_value(channel, Channel::Rgb, "RGB");
+    ui.radio_value(channel, Channel::Red, "Red");
+    ui.radio_value(channel, Channel::Green, "Green");
+    ui.radio_value(channel, Channel::Blue, "Blue");
+    ui.radio_value(channel, Channel::Luminance, "Luminance");
+    ui.radio_value(channel, Channel::FalseColor, "False color").on_hover_text(
+        "Luminance as a heatmap over 12 stops, from dark blue at 1/256 to red at 8, and white above",
+    );
+    ui.separator();
+
+    ui.checkbox(&mut settings.apply_to_saved_image, "Use for saved images");
+    if ui.button("Reset").clicked() {
+        *settings = DisplaySettings::default();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_near(expected: [f32; 3], actual: [f32; 3]) {
+        for c in 0..3 {
+            assert!(
+                (expected[c] - actual[c]).abs() < 1e-5,
+                "expected {expected:?} but got {actual:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn apply() {
+        let mut settings = DisplaySettings::default();
+        assert_near([0.25, 1., 0.], settings.apply([0.25, 4., -1.]));
+
+        settings.exposure = 1.;
+        assert_near([0.5, 1., 0.], settings.apply([0.25, 4., -1.]));
+
+        settings.tone_mapping = ToneMapping::Reinhard;
+        assert_near([1. / 3., 8. / 9., 0.], settings.apply([0.25, 4., -1.]));
+
+        settings.gamma = 2.;
+        settings.channel = Channel::Green;
+        assert_near([0.5; 3], settings.apply([0., 1. / 6., 0.]));
+
+        settings.tone_mapping = ToneMapping::AcesFilmic;
+        settings.channel = Channel::Rgb;
+        settings.exposure = 10.;
+        assert_near([1.; 3], settings.apply([1.; 3]));
+    }
+
+    #[test]
+    fn false_color() {
+        let settings = DisplaySettings {
+            channel: Channel::FalseColor,
+            ..Default::default()
+        };
+        assert_near(FALSE_COLORS[0], settings.apply([0.; 3]));
+        assert_near(FALSE_COLORS[0], settings.apply([1. / 256.; 3]));
+        // 1 is two thirds into the 12 stops, a third of the way from yellow to red
+        assert_near([1., 2. / 3., 0.], settings.apply([1.; 3]));
+        assert_near(FALSE_COLORS[5], settings.apply([16.; 3]));
+    }
+}
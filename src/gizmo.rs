@@ -0,0 +1,196 @@
+use std::error::Error;
+
+use eframe::egui::{Color32, Id, Rect, Sense, Stroke, Ui, Vec2};
+use solstrale::geo::vec3::Vec3;
+
+use crate::camera_yaml::{check_list_item_fields, update_list_item_fields};
+use crate::model::ray_cast::BoundingBox;
+use crate::model::transformation::Axis;
+use crate::{ErrorInfo, RenderControl};
+
+/// Size of the handles at the end of the gizmo axes, in points
+const HANDLE_SIZE: f32 = 14.;
+
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub enum GizmoMode {
+    #[default]
+    Translate,
+    Rotate,
+    Scale,
+}
+
+/// Handles drawn on the selected object to move, rotate and scale it in the viewport
+#[derive(Default)]
+pub struct Gizmo {
+    pub mode: GizmoMode,
+    /// Center of the object when the drag started, which it is rotated and scaled around
+    pivot: Option<Vec3>,
+}
+
+pub fn show_mode(ui: &mut Ui, render_control: &mut RenderControl) {
+    if render_control.selected_object.is_none() {
+        return;
+    }
+
+    let mode = &mut render_control.gizmo.mode;
+    ui.selectable_value(mode, GizmoMode::Translate, "Move")
+        .on_hover_text("Drag the handles of the selected object to move it along an axis");
+    ui.selectable_value(mode, GizmoMode::Rotate, "Rotate")
+        .on_hover_text(
+            "Drag across the handles of the selected object to rotate it around an axis",
+        );
+    ui.selectable_value(mode, GizmoMode::Scale, "Scale")
+        .on_hover_text("Drag the handles of the selected object outwards to make it larger");
+}
+
+/// Draws the gizmo on the selected object and applies drags of its handles to the scene. The
+/// scene is re-rendered while dragging, and the change is written to the scene yaml when the
+/// drag ends
+pub fn show(
+    ui: &Ui,
+    render_control: &mut RenderControl,
+    scene_yaml: &mut String,
+    rect: Rect,
+    error_info: &mut ErrorInfo,
+) {
+    if let Err(err) = show_gizmo(ui, render_control, scene_yaml, rect) {
+        render_control.gizmo.pivot = None;
+        error_info.handle(err);
+    }
+}
+
+fn show_gizmo(
+    ui: &Ui,
+    render_control: &mut RenderControl,
+    scene_yaml: &mut String,
+    rect: Rect,
+) -> Result<(), Box<dyn Error>> {
+    let variant_replaces_world = render_control.variant_replaces("world");
+    let (Some(scene), Some(orbit_camera), Some(index)) = (
+        &mut render_control.scene,
+        &render_control.orbit_camera,
+        render_control.selected_object,
+    ) else {
+        return Ok(());
+    };
+    let item_count = scene.world.len();
+    let Some(hittable) = scene.world.get_mut(index) else {
+        return Ok(());
+    };
+
+    let pivot = match render_control.gizmo.pivot {
+        Some(pivot) => pivot,
+        None => hittable.bounding_box()?.center(),
+    };
+    let aspect_ratio = rect.aspect_ratio() as f64;
+    let to_screen = |p: Vec3| {
+        orbit_camera
+            .project(p, aspect_ratio)
+            .map(|(x, y)| rect.lerp_inside(Vec2::new(x as f32, y as f32)))
+    };
+    let Some(center) = to_screen(pivot) else {
+        return Ok(());
+    };
+
+    // Keeps the gizmo the same size on screen regardless of the distance to the object
+    let length = (orbit_camera.look_from() - pivot).length()
+        * (orbit_camera.vertical_fov_degrees.to_radians() / 2.).tan()
+        * 0.25;
+
+    let painter = ui.painter_at(rect);
+    let mut drag = None;
+    for axis in Axis::ALL {
+        let Some(end) = to_screen(pivot + axis.unit() * length) else {
+            continue;
+        };
+        let color = axis_color(axis);
+        painter.line_segment([center, end], Stroke::new(2., color));
+        match render_control.gizmo.mode {
+            GizmoMode::Translate => painter.circle_filled(end, HANDLE_SIZE / 2., color),
+            GizmoMode::Rotate => {
+                painter.circle_stroke(end, HANDLE_SIZE / 2., Stroke::new(2., color))
+            }
+            GizmoMode::Scale => painter.rect_filled(
+                Rect::from_center_size(end, Vec2::splat(HANDLE_SIZE)),
+                0.,
+                color,
+            ),
+        };
+
+        let response = ui.interact(
+            Rect::from_center_size(end, Vec2::splat(HANDLE_SIZE)),
+            Id::new(("gizmo", axis as usize)),
+            Sense::drag(),
+        );
+        if response.dragged() || response.drag_stopped() {
+            drag = Some((
+                axis,
+                end - center,
+                response.drag_delta(),
+                response.drag_started(),
+                response.drag_stopped(),
+            ));
+        }
+    }
+
+    let Some((axis, screen_axis, delta, started, stopped)) = drag else {
+        return Ok(());
+    };
+
+    // The object is only moved when the move can be written to the scene yaml, which is
+    // reported once when the drag starts
+    let writable = if variant_replaces_world {
+        Err(
+            "The object can not be written to the scene yaml, the selected variant replaces the world"
+                .into(),
+        )
+    } else {
+        let keys: Vec<&str> = hittable
+            .transformed_fields()?
+            .iter()
+            .map(|(key, _)| *key)
+            .collect();
+        check_list_item_fields(scene_yaml, "world", index, item_count, &keys)
+    };
+    if let Err(err) = writable {
+        return if started { Err(err) } else { Ok(()) };
+    }
+    render_control.gizmo.pivot = Some(pivot);
+
+    // Axes pointing towards the camera are too short on screen to drag along
+    if screen_axis.length() > 4. && delta != Vec2::ZERO {
+        let along = (delta.dot(screen_axis) / screen_axis.length_sq()) as f64;
+        match render_control.gizmo.mode {
+            GizmoMode::Translate => hittable.translate(axis.unit() * (along * length)),
+            GizmoMode::Rotate => {
+                let across = delta.x * -screen_axis.y + delta.y * screen_axis.x;
+                hittable.rotate(axis, (across / screen_axis.length()) as f64 * 0.5, pivot)
+            }
+            GizmoMode::Scale => hittable.scale(along.exp(), pivot),
+        }
+        render_control.scene_updated = true;
+        render_control.render_requested = true;
+    }
+
+    if stopped {
+        render_control.gizmo.pivot = None;
+        let fields = hittable.transformed_fields()?;
+        *scene_yaml = update_list_item_fields(scene_yaml, "world", index, item_count, &fields)?;
+        // The rendered scene has the object moved as well
+        let source = &mut render_control.render_source;
+        if let Ok(yaml) =
+            update_list_item_fields(&source.scene_yaml, "world", index, item_count, &fields)
+        {
+            source.scene_yaml = yaml;
+        }
+    }
+    Ok(())
+}
+
+fn axis_color(axis: Axis) -> Color32 {
+    match axis {
+        Axis::X => Color32::from_rgb(230, 70, 70),
+        Axis::Y => Color32::from_rgb(80, 200, 80),
+        Axis::Z => Color32::from_rgb(70, 120, 230),
+    }
+}
@@ -0,0 +1,266 @@
+use std::sync::Arc;
+use std::sync::mpsc::{Receiver, TryRecvError, channel};
+use std::thread;
+
+use eframe::egui::{Color32, Grid, Pos2, Sense, Stroke, Ui, Vec2};
+use eframe::wgpu;
+
+use crate::RenderedImage;
+use crate::image_view::read_pixels;
+
+/// Number of bins in the histograms
+const BINS: usize = 64;
+/// Range of the histograms in stops, relative to a value of 1. Values outside of the range are
+/// counted in the first and last bins
+const MIN_STOPS: f32 = -10.;
+const MAX_STOPS: f32 = 4.;
+
+/// Histograms and statistics of the rendered image, for judging the exposure and finding broken
+/// materials
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageStatistics {
+    /// Histograms of red, green, blue and luminance, over the stops from `MIN_STOPS` to
+    /// `MAX_STOPS`. Zero values are left out, since they have no stop
+    pub histograms: [[u32; BINS]; 4],
+    pub min_luminance: f32,
+    pub max_luminance: f32,
+    pub mean_luminance: f32,
+    /// Pixels with a channel above 1, which clip unless tone mapped
+    pub clipped: usize,
+    pub nan: usize,
+    pub infinite: usize,
+    pub pixels: usize,
+}
+
+impl ImageStatistics {
+    /// Statistics of the RGBA pixels of an image. Pixels with NaN or infinite values are only
+    /// counted, and left out of the histograms and luminance
+    pub fn new(pixels: &[[f32; 4]]) -> ImageStatistics {
+        let mut statistics = ImageStatistics {
+            histograms: [[0; BINS]; 4],
+            min_luminance: f32::INFINITY,
+            max_luminance: f32::NEG_INFINITY,
+            mean_luminance: 0.,
+            clipped: 0,
+            nan: 0,
+            infinite: 0,
+            pixels: pixels.len(),
+        };
+
+        let mut sum = 0.;
+        let mut counted = 0;
+        for p in pixels {
+            let rgb = [p[0], p[1], p[2]];
+            if rgb.iter().any(|v| v.is_nan()) {
+                statistics.nan += 1;
+                continue;
+            }
+            if rgb.iter().any(|v| v.is_infinite()) {
+                statistics.infinite += 1;
+                continue;
+            }
+            if rgb.iter().any(|v| *v > 1.) {
+                statistics.clipped += 1;
+            }
+
+            let luminance = 0.2126 * rgb[0] + 0.7152 * rgb[1] + 0.0722 * rgb[2];
+            for (histogram, value) in statistics
+                .histograms
+                .iter_mut()
+                .zip([rgb[0], rgb[1], rgb[2], luminance])
+            {
+                if let Some(bin) = bin(value) {
+                    histogram[bin] += 1;
+                }
+            }
+            statistics.min_luminance = statistics.min_luminance.min(luminance);
+            statistics.max_luminance = statistics.max_luminance.max(luminance);
+            sum += luminance as f64;
+            counted += 1;
+        }
+
+        if counted > 0 {
+            statistics.mean_luminance = (sum / counted as f64) as f32;
+        } else {
+            statistics.min_luminance = 0.;
+            statistics.max_luminance = 0.;
+        }
+        statistics
+    }
+}
+
+fn bin(value: f32) -> Option<usize> {
+    if value <= 0. {
+        return None;
+    }
+    let t = (value.log2() - MIN_STOPS) / (MAX_STOPS - MIN_STOPS);
+    Some(((t * BINS as f32) as isize).clamp(0, BINS as isize - 1) as usize)
+}
+
+/// Computes the statistics of the rendered image on a separate thread whenever a new preview
+/// arrives, while the panel is shown
+#[derive(Default)]
+pub struct StatisticsPanel {
+    statistics: Option<ImageStatistics>,
+    /// Buffer the statistics are computed, or being computed, for
+    buffer: Option<Arc<wgpu::Buffer>>,
+    receiver: Option<Receiver<ImageStatistics>>,
+}
+
+impl StatisticsPanel {
+    fn update(&mut self, ui: &Ui, rendered_image: &RenderedImage) {
+        if let Some(receiver) = &self.receiver {
+            match receiver.try_recv() {
+                Ok(statistics) => {
+                    self.statistics = Some(statistics);
+                    self.receiver = None;
+                }
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => self.receiver = None,
+            }
+        }
+
+        let (Some(resources), Some(buffer)) = (
+            &rendered_image.render_resources,
+            &rendered_image.output_buffer,
+        ) else {
+            return;
+        };
+        if self.buffer.as_ref().is_some_and(|b| Arc::ptr_eq(b, buffer)) {
+            return;
+        }
+        self.buffer = Some(buffer.clone());
+
+        let (sender, receiver) = channel();
+        self.receiver = Some(receiver);
+        let resources = resources.clone();
+        let buffer = buffer.clone();
+        let count = rendered_image.width as u64 * rendered_image.height as u64;
+        let ctx = ui.ctx().clone();
+        thread::spawn(move || {
+            let pixels = read_pixels(&resources, &buffer, 0, count);
+            sender.send(ImageStatistics::new(&pixels)).ok();
+            ctx.request_repaint();
+        });
+    }
+}
+
+pub fn show(ui: &mut Ui, panel: &mut StatisticsPanel, rendered_image: &RenderedImage) {
+    panel.update(ui, rendered_image);
+
+    let Some(statistics) = &panel.statistics else {
+        ui.label("No image is rendered");
+        return;
+    };
+
+    show_histograms(ui, &statistics.histograms);
+    ui.label(format!(
+        "{} to {} stops",
+        MIN_STOPS as i32, MAX_STOPS as i32
+    ));
+
+    Grid::new("image-statistics").num_columns(2).show(ui, |ui| {
+        ui.label("Min luminance");
+        ui.label(format!("{:.4}", statistics.min_luminance));
+        ui.end_row();
+
+        ui.label("Max luminance");
+        ui.label(format!("{:.4}", statistics.max_luminance));
+        ui.end_row();
+
+        ui.label("Mean luminance");
+        ui.label(format!("{:.4}", statistics.mean_luminance));
+        ui.end_row();
+
+        ui.label("Clipped pixels")
+            .on_hover_text("Pixels with a channel above 1");
+        ui.label(percentage(statistics.clipped, statistics.pixels));
+        ui.end_row();
+
+        ui.label("NaN pixels")
+            .on_hover_text("Usually caused by a broken material or model");
+        ui.label(percentage(statistics.nan, statistics.pixels));
+        ui.end_row();
+
+        ui.label("Infinite pixels");
+        ui.label(percentage(statistics.infinite, statistics.pixels));
+        ui.end_row();
+    });
+}
+
+fn percentage(count: usize, total: usize) -> String {
+    format!(
+        "{count} ({:.2}%)",
+        count as f64 / total.max(1) as f64 * 100.
+    )
+}
+
+/// Draws the histograms on top of each other, each scaled to its own highest bin
+fn show_histograms(ui: &mut Ui, histograms: &[[u32; BINS]; 4]) {
+    let (rect, _) = ui.allocate_exact_size(Vec2::new(256., 100.), Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2., ui.visuals().extreme_bg_color);
+
+    let colors = [
+        Color32::from_rgb(230, 70, 70),
+        Color32::from_rgb(80, 200, 80),
+        Color32::from_rgb(70, 120, 230),
+        ui.visuals().text_color(),
+    ];
+    for (histogram, color) in histograms.iter().zip(colors) {
+        let highest = *histogram.iter().max().unwrap_or(&0);
+        if highest == 0 {
+            continue;
+        }
+        let points: Vec<Pos2> = histogram
+            .iter()
+            .enumerate()
+            .map(|(i, count)| {
+                rect.lerp_inside(Vec2::new(
+                    (i as f32 + 0.5) / BINS as f32,
+                    1. - *count as f32 / highest as f32,
+                ))
+            })
+            .collect();
+        painter.line(points, Stroke::new(1.5, color));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn statistics() {
+        let statistics = ImageStatistics::new(&[
+            [1., 1., 1., 1.],
+            [0.25, 0.25, 0.25, 1.],
+            [2., 0., 0., 1.],
+            [f32::NAN, 0., 0., 1.],
+            [0., f32::INFINITY, 0., 1.],
+            [0., 0., 0., 1.],
+        ]);
+
+        assert_eq!(6, statistics.pixels);
+        assert_eq!(1, statistics.nan);
+        assert_eq!(1, statistics.infinite);
+        assert_eq!(1, statistics.clipped);
+        assert_eq!(0., statistics.min_luminance);
+        assert_eq!(1., statistics.max_luminance);
+        assert!((statistics.mean_luminance - (1. + 0.25 + 0.4252) / 4.).abs() < 1e-6);
+
+        // 1 is 10 stops into the 14, and 0.25 two stops less
+        let one = 10 * BINS / 14;
+        let quarter = 8 * BINS / 14;
+        assert_eq!(1, statistics.histograms[1][one]);
+        assert_eq!(1, statistics.histograms[1][quarter]);
+        assert_eq!(2, statistics.histograms[1].iter().sum::<u32>());
+        // 2 is counted in the red histogram, above 1
+        assert_eq!(3, statistics.histograms[0].iter().sum::<u32>());
+        assert_eq!(1, statistics.histograms[0][bin(2.).unwrap()]);
+        assert!(bin(2.).unwrap() > one);
+        assert_eq!(Some(BINS - 1), bin(1000.));
+        assert_eq!(Some(0), bin(1e-9));
+        assert_eq!(None, bin(0.));
+    }
+}
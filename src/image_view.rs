@@ -0,0 +1,318 @@
+use std::sync::Arc;
+use std::sync::mpsc::{Receiver, TryRecvError, channel};
+use std::thread;
+
+use eframe::egui::{FontId, Rect, Response, Ui, Vec2};
+use eframe::wgpu;
+use solstrale::util::wgpu_util::get_result_from_buffer;
+
+use crate::{RenderControl, RenderResources, RenderedImage, display_settings};
+
+/// Range of the zoom while inspecting the image
+const MIN_ZOOM: f32 = 0.1;
+const MAX_ZOOM: f32 = 256.;
+
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub enum DisplayMode {
+    /// The image is scaled to fit the viewport, keeping its aspect ratio
+    #[default]
+    Fit,
+    /// Each pixel of the image is shown on one pixel of the screen
+    Actual,
+}
+
+/// How the rendered image is placed in the viewport
+pub struct ImageView {
+    pub mode: DisplayMode,
+    /// Zooming, panning and inspecting pixels with the mouse, instead of controlling the camera
+    inspect: bool,
+    zoom: f32,
+    pan: Vec2,
+    /// Last pixel read back from the output buffer, with the buffer it was read from
+    pixel: Option<(Arc<wgpu::Buffer>, u64, [f32; 4])>,
+    /// Pixel being read back on a separate thread
+    reading: Option<(Arc<wgpu::Buffer>, u64, Receiver<[f32; 4]>)>,
+}
+
+impl Default for ImageView {
+    fn default() -> Self {
+        ImageView {
+            mode: DisplayMode::default(),
+            inspect: false,
+            zoom: 1.,
+            pan: Vec2::ZERO,
+            pixel: None,
+            reading: None,
+        }
+    }
+}
+
+impl ImageView {
+    pub fn is_inspecting(&self) -> bool {
+        self.inspect
+    }
+
+    /// Switches between inspecting the image and controlling the camera. The zoom and pan are
+    /// reset when going back to the camera, which needs to see the whole image
+    pub fn set_inspect(&mut self, inspect: bool) {
+        self.inspect = inspect;
+        if !inspect {
+            self.zoom = 1.;
+            self.pan = Vec2::ZERO;
+            self.pixel = None;
+            self.reading = None;
+        }
+    }
+
+    /// Area of the viewport the image is painted in. Images with another aspect ratio than the
+    /// viewport are letterboxed. The size is the full resolution of the image, so that it keeps
+    /// its size on screen while rendering at a reduced resolution
+    pub fn image_rect(&self, viewport: Rect, image_size: Vec2, pixels_per_point: f32) -> Rect {
+        if image_size.x <= 0. || image_size.y <= 0. {
+            return viewport;
+        }
+
+        let size = match self.mode {
+            DisplayMode::Fit => {
+                image_size * (viewport.width() / image_size.x).min(viewport.height() / image_size.y)
+            }
+            DisplayMode::Actual => image_size / pixels_per_point,
+        };
+        Rect::from_center_size(viewport.center() + self.pan, size * self.zoom)
+    }
+
+    /// Scrolling zooms around the cursor and dragging pans. `rect` is where the image is
+    /// painted, as given by [`ImageView::image_rect`]
+    pub fn interact(&mut self, ui: &Ui, response: &Response, rect: Rect) {
+        let scroll = ui.input(|i| i.smooth_scroll_delta.y);
+        if scroll != 0.
+            && let Some(pointer) = response.hover_pos()
+        {
+            let zoom = (self.zoom * (scroll * 0.002).exp()).clamp(MIN_ZOOM, MAX_ZOOM);
+            // Keeps the point of the image under the cursor in place
+            let center = pointer + (rect.center() - pointer) * (zoom / self.zoom);
+            self.pan += center - rect.center();
+            self.zoom = zoom;
+        }
+
+        if response.dragged() {
+            self.pan += response.drag_delta();
+        }
+    }
+
+    /// Shows the coordinates and the raw values of the pixel under the cursor in the corner of
+    /// the viewport. The pixel is read back on a separate thread, one at a time, since the read
+    /// waits for the renderer
+    pub fn show_pixel(
+        &mut self,
+        ui: &Ui,
+        response: &Response,
+        rect: Rect,
+        viewport: Rect,
+        rendered_image: &RenderedImage,
+    ) {
+        let (Some(pointer), Some(resources), Some(buffer)) = (
+            response.hover_pos(),
+            &rendered_image.render_resources,
+            &rendered_image.output_buffer,
+        ) else {
+            return;
+        };
+        let Some((x, y)) = pixel_at(rect, pointer - rect.min, rendered_image) else {
+            return;
+        };
+
+        let index = y as u64 * rendered_image.width as u64 + x as u64;
+        if let Some((b, i, receiver)) = &self.reading {
+            match receiver.try_recv() {
+                Ok(value) => {
+                    self.pixel = Some((b.clone(), *i, value));
+                    self.reading = None;
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => self.reading = None,
+            }
+        }
+        let read = self
+            .pixel
+            .as_ref()
+            .is_some_and(|(b, i, _)| Arc::ptr_eq(b, buffer) && *i == index);
+        if !read && self.reading.is_none() {
+            let (sender, receiver) = channel();
+            self.reading = Some((buffer.clone(), index, receiver));
+            let resources = resources.clone();
+            let buffer = buffer.clone();
+            let ctx = ui.ctx().clone();
+            thread::spawn(move || {
+                sender.send(read_pixel(&resources, &buffer, index)).ok();
+                ctx.request_repaint();
+            });
+        }
+
+        // The value of the pixel from an earlier sample is shown until the current one is read
+        let text = match &self.pixel {
+            Some((_, i, value)) if *i == index => format!(
+                "{x}, {y}  R {:.4}  G {:.4}  B {:.4}",
+                value[0], value[1], value[2]
+            ),
+            _ => format!("{x}, {y}"),
+        };
+        let painter = ui.painter_at(viewport);
+        let galley =
+            painter.layout_no_wrap(text, FontId::monospace(12.), ui.visuals().text_color());
+        let pos = viewport.left_bottom() + Vec2::new(8., -8. - galley.size().y);
+        painter.rect_filled(
+            Rect::from_min_size(pos, galley.size()).expand(4.),
+            2.,
+            ui.visuals().extreme_bg_color.gamma_multiply(0.8),
+        );
+        painter.galley(pos, galley, ui.visuals().text_color());
+    }
+}
+
+/// Pixel of the image at an offset from the top left of where it is painted
+fn pixel_at(rect: Rect, offset: Vec2, rendered_image: &RenderedImage) -> Option<(u32, u32)> {
+    let x = (offset.x / rect.width() * rendered_image.width as f32).floor();
+    let y = (offset.y / rect.height() * rendered_image.height as f32).floor();
+    (x >= 0. && y >= 0. && x < rendered_image.width as f32 && y < rendered_image.height as f32)
+        .then_some((x as u32, y as u32))
+}
+
+/// Reads the RGBA values of a single pixel back from the output buffer of the renderer
+fn read_pixel(resources: &RenderResources, buffer: &wgpu::Buffer, index: u64) -> [f32; 4] {
+    read_pixels(resources, buffer, index, 1)[0]
+}
+
+/// Reads the RGBA values of `count` pixels, starting at `first`, back from the output buffer of
+/// the renderer. Blocks until the GPU is done
+pub fn read_pixels(
+    resources: &RenderResources,
+    buffer: &wgpu::Buffer,
+    first: u64,
+    count: u64,
+) -> Vec<[f32; 4]> {
+    let pixel_size = 4 * size_of::<f32>() as u64;
+    let staging_buffer = resources.device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Pixel Staging Buffer"),
+        size: count * pixel_size,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = resources
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    encoder.copy_buffer_to_buffer(
+        buffer,
+        first * pixel_size,
+        &staging_buffer,
+        0,
+        count * pixel_size,
+    );
+    resources.queue.submit(Some(encoder.finish()));
+
+    get_result_from_buffer(&resources.device, &staging_buffer)
+}
+
+pub fn show_menu(
+    ui: &mut Ui,
+    render_control: &mut RenderControl,
+    rendered_image: &mut RenderedImage,
+) {
+    ui.menu_button("View", |ui| {
+        let view = &mut render_control.image_view;
+        ui.radio_value(&mut view.mode, DisplayMode::Fit, "Fit to viewport")
+            .on_hover_text("Scale the image to fit the viewport");
+        ui.radio_value(&mut view.mode, DisplayMode::Actual, "Actual size (1:1)")
+            .on_hover_text("Show each pixel of the image on one pixel of the screen");
+        ui.separator();
+
+        let mut inspect = view.inspect;
+        if ui
+            .checkbox(&mut inspect, "Inspect image")
+            .on_hover_text(
+                "Scroll to zoom, drag to pan and hover to see the values of the pixels, instead of controlling the camera",
+            )
+            .changed()
+        {
+            view.set_inspect(inspect);
+        }
+        ui.checkbox(&mut render_control.render_region.selecting, "Render region")
+            .on_hover_text(
+                "Drag a rectangle in the viewport to render only that region at full quality, over the rest of the image. Render renders the full image again",
+            );
+        ui.separator();
+
+        ui.menu_button("Display", |ui| {
+            display_settings::show(ui, &mut rendered_image.display_settings)
+        })
+        .response
+        .on_hover_text("Exposure, tone mapping and channels of the image in the viewport");
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use eframe::egui::Pos2;
+
+    use super::*;
+
+    #[test]
+    fn image_rect() {
+        let viewport = Rect::from_min_size(Pos2::new(10., 20.), Vec2::new(400., 200.));
+        let mut view = ImageView::default();
+
+        // Same aspect ratio fills the viewport
+        let rect = view.image_rect(viewport, Vec2::new(200., 100.), 1.);
+        assert_eq!(viewport, rect);
+
+        // Taller images are letterboxed on the sides
+        let rect = view.image_rect(viewport, Vec2::new(100., 100.), 1.);
+        assert_eq!(
+            Rect::from_min_size(Pos2::new(110., 20.), Vec2::new(200., 200.)),
+            rect
+        );
+
+        // Zooming scales around the center, and panning moves the image
+        view.zoom = 2.;
+        view.pan = Vec2::new(10., 0.);
+        let rect = view.image_rect(viewport, Vec2::new(200., 100.), 1.);
+        assert_eq!(
+            Rect::from_min_size(Pos2::new(-180., -80.), Vec2::new(800., 400.)),
+            rect
+        );
+
+        view.set_inspect(false);
+        view.mode = DisplayMode::Actual;
+        let rect = view.image_rect(viewport, Vec2::new(100., 100.), 2.);
+        assert_eq!(
+            Rect::from_min_size(Pos2::new(185., 95.), Vec2::new(50., 50.)),
+            rect
+        );
+    }
+
+    #[test]
+    fn pixel_at() {
+        let rect = Rect::from_min_size(Pos2::new(10., 20.), Vec2::new(200., 100.));
+        let image = RenderedImage {
+            width: 20,
+            height: 10,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            Some((0, 0)),
+            super::pixel_at(rect, Vec2::new(0., 0.), &image)
+        );
+        assert_eq!(
+            Some((19, 9)),
+            super::pixel_at(rect, Vec2::new(199., 99.), &image)
+        );
+        assert_eq!(
+            Some((5, 2)),
+            super::pixel_at(rect, Vec2::new(55., 25.), &image)
+        );
+        assert_eq!(None, super::pixel_at(rect, Vec2::new(200., 0.), &image));
+        assert_eq!(None, super::pixel_at(rect, Vec2::new(-1., 0.), &image));
+    }
+}
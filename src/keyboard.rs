@@ -6,6 +6,10 @@ pub fn is_ctrl_r(ui: &Ui) -> bool {
     is_key_combo(ui, egui::Key::R, Modifiers::CTRL)
 }
 
+pub fn is_ctrl_b(ui: &Ui) -> bool {
+    is_key_combo(ui, egui::Key::B, Modifiers::CTRL)
+}
+
 pub fn is_ctrl_space(ui: &Ui) -> bool {
     is_key_combo(ui, egui::Key::Space, Modifiers::CTRL)
 }
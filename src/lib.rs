@@ -1,5 +1,15 @@
+use crate::camera_panel::CameraSettings;
+use crate::camera_recorder::CameraRecorder;
+use crate::display_settings::DisplaySettings;
+use crate::gizmo::Gizmo;
+use crate::image_view::ImageView;
+use crate::model::fly_camera::FlyCamera;
 use crate::model::orbit_camera::OrbitCamera;
-use eframe::egui::Vec2;
+use crate::model::scene_override::{PathSegment, SceneOverride};
+use crate::render_controls::{RenderState, RenderTarget};
+use crate::render_region::RenderRegion;
+use crate::snapshots::{RenderSource, Snapshots};
+use eframe::egui::{Rect, Vec2};
 use eframe::wgpu;
 use once_cell::sync::Lazy;
 use solstrale::renderer::RenderProgress;
@@ -11,16 +21,29 @@ use std::time::Duration;
 use model::scene::Scene;
 use std::sync::Mutex;
 
+pub mod camera_bookmarks;
+pub mod camera_menu;
+pub mod camera_panel;
+pub mod camera_recorder;
+pub mod camera_yaml;
+pub mod display_settings;
+pub mod gizmo;
 pub mod help;
+pub mod image_statistics;
+pub mod image_view;
 pub mod keyboard;
 pub mod load_scene;
 pub mod loading_output;
 pub mod model;
 pub mod render_button;
+pub mod render_controls;
 pub mod render_output;
+pub mod render_region;
 pub mod reset_confirm;
 pub mod save_image;
 pub mod save_scene;
+pub mod snapshots;
+pub mod variant_picker;
 pub mod yaml_editor;
 
 pub static DEFAULT_SCENE: Lazy<String> =
@@ -54,15 +77,94 @@ pub struct RenderControl {
     pub render_receiver: Option<Receiver<RenderMessage>>,
     pub camera_config_sender: Option<Sender<solstrale::camera::CameraConfig>>,
     pub render_requested: bool,
+    /// The next render continues a paused one
+    pub resume_requested: bool,
+    pub render_state: RenderState,
+    pub render_target: RenderTarget,
+    /// Samples per pixel of the scene being rendered
+    pub samples_per_pixel: u32,
+    /// Samples of the paused image, which is shown until a resumed render catches up with it.
+    /// Zero when not resuming
+    pub resumed_samples: u32,
+    /// Seconds spent rendering before the current run of the renderer
+    pub render_seconds: f64,
+    /// Time the current run of the renderer started, in the time of the egui input
+    pub render_started_at: f64,
     pub loading_scene: bool,
     pub initial_render_started: bool,
     pub previous_frame_render_size: Vec2,
+    /// Screen size given to the renderer, which is smaller than the viewport while the camera
+    /// moves
+    pub render_size: Vec2,
+    /// Size of the image being rendered, which depends on the render configuration of the scene
+    pub image_size: Vec2,
+    /// Size of the image at full resolution, which it is shown at while rendering at a reduced
+    /// resolution
+    pub full_image_size: Vec2,
+    pub image_view: ImageView,
+    /// Rendering at the interaction resolution, until the camera settles
+    pub reduced_resolution: bool,
+    /// Time of the last camera movement, in the time of the egui input
+    pub last_camera_move: f64,
     pub orbit_camera: Option<OrbitCamera>,
+    /// Set while flying, the orbit camera follows it so that it always holds the current view
+    pub fly_camera: Option<FlyCamera>,
+    pub camera_mode: CameraMode,
+    pub camera_settings: CameraSettings,
+    /// Index in the `world` of the scene of the object picked in the viewport
+    pub selected_object: Option<usize>,
+    pub gizmo: Gizmo,
     pub scene: Option<Scene>,
     pub camera_updated: bool,
+    /// The scene model was changed directly, like when dragging an object, so it is
+    /// re-rendered as is instead of being parsed again from the yaml
+    pub scene_updated: bool,
+    pub variant: Option<String>,
+    pub camera: Option<String>,
+    pub camera_recorder: CameraRecorder,
+    pub snapshots: Snapshots,
+    pub render_source: RenderSource,
+    /// View of a restored snapshot, which replaces the camera of the scene once it is parsed
+    pub restored_view: Option<OrbitCamera>,
+    pub render_region: RenderRegion,
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub enum CameraMode {
+    #[default]
+    Orbit,
+    Fly,
+}
+
+impl RenderControl {
+    /// Overrides of the scene for the selections made in the UI
+    pub fn scene_overrides(&self) -> Vec<SceneOverride> {
+        self.camera
+            .iter()
+            .map(|name| SceneOverride {
+                path: vec![PathSegment::Key("active_camera".to_string())],
+                value: name.as_str().into(),
+            })
+            .collect()
+    }
+
+    /// The selected variant replaces the top level `key` of the scene, so the scene does not
+    /// match what is written under the key in the scene yaml
+    pub fn variant_replaces(&self, key: &str) -> bool {
+        let (Some(scene), Some(variant)) = (&self.scene, &self.variant) else {
+            return false;
+        };
+        scene
+            .variants
+            .get(variant)
+            .is_some_and(|v| v.get(key).is_some())
+    }
 }
 
 pub enum RenderMessage {
+    /// The scene is created and the renderer starts rendering an image of the given size, with
+    /// the given samples per pixel
+    Started(usize, usize, u32),
     SampleRendered(RenderProgress),
     Error(String),
 }
@@ -70,17 +172,35 @@ pub enum RenderMessage {
 pub struct RenderResources {
     pub pipeline: wgpu::RenderPipeline,
     pub bind_group_layout: wgpu::BindGroupLayout,
-    pub viewport_size_buffer: wgpu::Buffer,
+    pub display_buffer: wgpu::Buffer,
+    pub composite_pipeline: wgpu::ComputePipeline,
+    pub composite_bind_group_layout: wgpu::BindGroupLayout,
     pub target_format: wgpu::TextureFormat,
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
 }
 
+/// Layout of the `Display` uniform of the shader in `render_output`
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct DisplayUniform {
+    pub image_size: [f32; 2],
+    pub uv_min: [f32; 2],
+    pub uv_max: [f32; 2],
+    pub exposure: f32,
+    pub tone_mapping: u32,
+    pub gamma: f32,
+    pub channel: u32,
+}
+
 pub struct RenderCallback {
     pub resources: Arc<RenderResources>,
     pub output_buffer: Arc<wgpu::Buffer>,
     pub width: u32,
     pub height: u32,
+    /// Part of the image to paint, from 0, 0 at the top left to 1, 1 at the bottom right
+    pub uv_rect: Rect,
+    pub display_settings: DisplaySettings,
     pub bind_group: Arc<Mutex<Option<Arc<wgpu::BindGroup>>>>,
 }
 
@@ -93,10 +213,19 @@ impl eframe::egui_wgpu::CallbackTrait for RenderCallback {
         _egui_encoder: &mut wgpu::CommandEncoder,
         _callback_resources: &mut eframe::egui_wgpu::CallbackResources,
     ) -> Vec<wgpu::CommandBuffer> {
+        let (exposure, tone_mapping, gamma, channel) = self.display_settings.uniform();
         queue.write_buffer(
-            &self.resources.viewport_size_buffer,
+            &self.resources.display_buffer,
             0,
-            bytemuck::cast_slice(&[self.width as f32, self.height as f32]),
+            bytemuck::bytes_of(&DisplayUniform {
+                image_size: [self.width as f32, self.height as f32],
+                uv_min: self.uv_rect.min.into(),
+                uv_max: self.uv_rect.max.into(),
+                exposure,
+                tone_mapping,
+                gamma,
+                channel,
+            }),
         );
 
         let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
@@ -105,7 +234,7 @@ impl eframe::egui_wgpu::CallbackTrait for RenderCallback {
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
-                    resource: self.resources.viewport_size_buffer.as_entire_binding(),
+                    resource: self.resources.display_buffer.as_entire_binding(),
                 },
                 wgpu::BindGroupEntry {
                     binding: 1,
@@ -135,10 +264,13 @@ pub struct RenderedImage {
     pub output_buffer: Option<Arc<wgpu::Buffer>>,
     pub render_resources: Option<Arc<RenderResources>>,
     pub progress: f64,
+    /// Samples per pixel rendered
+    pub samples: u32,
     pub fps: f64,
     pub estimated_time_left: Duration,
     pub width: u32,
     pub height: u32,
+    pub display_settings: DisplaySettings,
 }
 
 impl Default for RenderedImage {
@@ -147,10 +279,12 @@ impl Default for RenderedImage {
             output_buffer: None,
             render_resources: None,
             progress: 0.0,
+            samples: 0,
             fps: 0.0,
             estimated_time_left: Duration::default(),
             width: 0,
             height: 0,
+            display_settings: DisplaySettings::default(),
         }
     }
 }
@@ -2,12 +2,15 @@ use std::collections::HashMap;
 use std::error::Error;
 
 use serde::{Deserialize, Serialize};
+use solstrale::geo::Aabb;
+use solstrale::geo::vec3::Vec3;
 use solstrale::hittable::Hittables;
 
 use crate::model::FieldType::{Normal, Optional, OptionalList};
 use crate::model::material::Material;
 use crate::model::pos::Pos;
-use crate::model::transformation::{Transformation, create_transformation};
+use crate::model::ray_cast::{BoundingBox, RayCast, aabb_from_points, box_corners, ray_cast_box};
+use crate::model::transformation::{Transformation, create_transformation, transformer};
 use crate::model::{Creator, CreatorContext, DocumentationStructure, FieldInfo, HelpDocumentation};
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
@@ -35,6 +38,32 @@ impl Creator<Vec<Hittables>> for Box {
     }
 }
 
+impl RayCast for Box {
+    fn ray_cast(
+        &self,
+        origin: Vec3,
+        direction: Vec3,
+    ) -> Result<Option<f64>, std::boxed::Box<dyn Error>> {
+        Ok(ray_cast_box(
+            origin,
+            direction,
+            (&self.a).into(),
+            (&self.b).into(),
+            &transformer(&self.transformations)?,
+        ))
+    }
+}
+
+impl BoundingBox for Box {
+    fn bounding_box(&self) -> Result<Aabb, std::boxed::Box<dyn Error>> {
+        Ok(aabb_from_points(&box_corners(
+            (&self.a).into(),
+            (&self.b).into(),
+            &transformer(&self.transformations)?,
+        )))
+    }
+}
+
 impl HelpDocumentation for Box {
     fn get_documentation_structure(depth: u8) -> DocumentationStructure {
         DocumentationStructure {
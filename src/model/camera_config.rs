@@ -4,8 +4,11 @@ use std::error::Error;
 use serde::{Deserialize, Serialize};
 
 use crate::model::FieldType::{Normal, Optional};
+use crate::model::camera_path::CameraPath;
+use crate::model::lens::Lens;
+use crate::model::orbit_camera::rolled_up;
 use crate::model::{
-    Creator, CreatorContext, DocumentationStructure, FieldInfo, HelpDocumentation, Pos,
+    Creator, CreatorContext, DocumentationStructure, FieldInfo, HelpDocumentation, ModelError, Pos,
 };
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
@@ -15,11 +18,41 @@ pub struct CameraConfig {
     pub vertical_fov_degrees: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub aperture_size: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub focus_distance: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lens: Option<Lens>,
     pub look_from: Pos,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub look_at: Option<Pos>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub up: Option<Pos>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub roll_degrees: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<CameraPath>,
+}
+
+impl CameraConfig {
+    /// Resolves the camera path, if any, to a static camera for the given frame
+    pub fn at_frame(&self, frame: f64) -> Result<CameraConfig, Box<dyn Error>> {
+        let Some(path) = &self.path else {
+            return Ok(self.clone());
+        };
+        let point = path.at_frame(frame)?;
+
+        Ok(CameraConfig {
+            vertical_fov_degrees: point.vertical_fov_degrees.or(self.vertical_fov_degrees),
+            aperture_size: point.aperture_size.or(self.aperture_size),
+            focus_distance: self.focus_distance,
+            lens: self.lens,
+            look_from: point.look_from,
+            look_at: point.look_at.or(self.look_at),
+            up: self.up,
+            roll_degrees: self.roll_degrees,
+            path: None,
+        })
+    }
 }
 
 impl Creator<solstrale::camera::CameraConfig> for CameraConfig {
@@ -27,12 +60,51 @@ impl Creator<solstrale::camera::CameraConfig> for CameraConfig {
         &self,
         ctx: &CreatorContext,
     ) -> Result<solstrale::camera::CameraConfig, Box<dyn Error>> {
+        let (vertical_fov_degrees, aperture_size) = match &self.lens {
+            None => (self.vertical_fov_degrees, self.aperture_size),
+            Some(lens) => {
+                if self.vertical_fov_degrees.is_some() {
+                    return Err(Box::new(ModelError::new(
+                        "Camera should not define both vertical_fov_degrees and lens",
+                    )));
+                }
+                let lens_aperture = lens.aperture_size()?;
+                if lens_aperture.is_some() && self.aperture_size.is_some() {
+                    return Err(Box::new(ModelError::new(
+                        "Camera should not define both aperture_size and lens f_stop",
+                    )));
+                }
+                (
+                    Some(lens.vertical_fov_degrees()?),
+                    lens_aperture.or(self.aperture_size),
+                )
+            }
+        };
+
+        // The renderer focuses at the distance to look_at, so look_at is moved along the
+        // view direction to the focus distance
+        let look_from = self.look_from.create(ctx)?;
+        let mut look_at = self.look_at.unwrap_or_default().create(ctx)?;
+        if let Some(focus_distance) = self.focus_distance {
+            if focus_distance <= 0. {
+                return Err(Box::new(ModelError::new(
+                    "Camera focus_distance must be greater than 0",
+                )));
+            }
+            look_at = look_from + (look_at - look_from).unit() * focus_distance;
+        }
+
+        let mut up = self.up.unwrap_or(Pos::new(0., 1., 0.)).create(ctx)?;
+        if let Some(roll) = self.roll_degrees {
+            up = rolled_up(up, look_from, look_at, roll.to_radians());
+        }
+
         Ok(solstrale::camera::CameraConfig {
-            vertical_fov_degrees: self.vertical_fov_degrees.unwrap_or(60.),
-            aperture_size: self.aperture_size.unwrap_or(0.),
-            look_from: self.look_from.create(ctx)?,
-            look_at: self.look_at.unwrap_or_default().create(ctx)?,
-            up: self.up.unwrap_or(Pos::new(0., 1., 0.)).create(ctx)?,
+            vertical_fov_degrees: vertical_fov_degrees.unwrap_or(60.),
+            aperture_size: aperture_size.unwrap_or(0.),
+            look_from,
+            look_at,
+            up,
         })
     }
 }
@@ -52,6 +124,16 @@ impl HelpDocumentation for CameraConfig {
                     Optional,
                     "The radius of the aperture. Defaults to 0"
                 )),
+                ("focus_distance".to_string(), FieldInfo::new_simple(
+                    "Distance from the camera to where the image is sharpest when aperture_size is above 0. Defaults to the distance to look_at",
+                    Optional,
+                    "Distance to the focus plane"
+                )),
+                ("lens".to_string(), FieldInfo::new(
+                    "Physical lens parameters, converted to the field of view and aperture",
+                    Optional,
+                    Lens::get_documentation_structure(depth + 1)
+                )),
                 ("look_from".to_string(), FieldInfo::new(
                     "Position where the camera is located",
                     Normal,
@@ -66,6 +148,16 @@ impl HelpDocumentation for CameraConfig {
                     "A vector pointing in the 'up' direction of the camera. Default to 0, 1, 0 to have y pointing upwards",
                     Optional,
                     Pos::get_documentation_structure(depth + 1)
+                )),
+                ("roll_degrees".to_string(), FieldInfo::new_simple(
+                    "Rotation of the camera around the view direction in degrees, where positive tilts the camera to the right. The camera orbits around up, which is kept as is. Defaults to 0",
+                    Optional,
+                    "Rotation around the view direction"
+                )),
+                ("path".to_string(), FieldInfo::new(
+                    "Animates the camera along splines by frame index, for fly-throughs in batch rendering",
+                    Optional,
+                    CameraPath::get_documentation_structure(depth + 1)
                 ))
             ]),
         }
@@ -0,0 +1,319 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use serde::{Deserialize, Serialize};
+use solstrale::geo::vec3::Vec3;
+
+use crate::model::FieldType::{Normal, Optional, OptionalList};
+use crate::model::pos::Pos;
+use crate::model::{DocumentationStructure, FieldInfo, HelpDocumentation, ModelError};
+
+/// Number of samples per spline segment used when measuring the length of the spline
+const ARC_LENGTH_SAMPLES: usize = 64;
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct CameraPath {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_frame: Option<f64>,
+    pub end_frame: f64,
+    pub look_from: Spline,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub look_at: Option<Spline>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub vertical_fov_degrees: Vec<f64>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub aperture_size: Vec<f64>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Spline {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub catmull_rom: Option<Vec<Pos>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bezier: Option<Vec<Pos>>,
+}
+
+/// Camera values at a given frame of the path
+pub struct CameraPathPoint {
+    pub look_from: Pos,
+    pub look_at: Option<Pos>,
+    pub vertical_fov_degrees: Option<f64>,
+    pub aperture_size: Option<f64>,
+}
+
+impl CameraPath {
+    pub fn at_frame(&self, frame: f64) -> Result<CameraPathPoint, Box<dyn Error>> {
+        let start_frame = self.start_frame.unwrap_or(0.);
+        if self.end_frame <= start_frame {
+            return Err(Box::new(ModelError::new(
+                "Camera path end_frame must be greater than start_frame",
+            )));
+        }
+        let t = ((frame - start_frame) / (self.end_frame - start_frame)).clamp(0., 1.);
+
+        Ok(CameraPathPoint {
+            look_from: self.look_from.at(t)?.into(),
+            look_at: match &self.look_at {
+                Some(s) => Some(s.at(t)?.into()),
+                None => None,
+            },
+            vertical_fov_degrees: interpolate_values(&self.vertical_fov_degrees, t),
+            aperture_size: interpolate_values(&self.aperture_size, t),
+        })
+    }
+}
+
+/// Values are spread evenly over the path and linearly interpolated between
+fn interpolate_values(values: &[f64], t: f64) -> Option<f64> {
+    match values {
+        [] => None,
+        [v] => Some(*v),
+        _ => {
+            let pos = t * (values.len() - 1) as f64;
+            let i = (pos.floor() as usize).min(values.len() - 2);
+            let f = pos - i as f64;
+            Some(values[i] + (values[i + 1] - values[i]) * f)
+        }
+    }
+}
+
+impl Spline {
+    /// Position at the fraction `t` of the total length of the spline, so that the camera
+    /// moves with constant speed regardless of how the control points are spaced
+    pub fn at(&self, t: f64) -> Result<Vec3, Box<dyn Error>> {
+        let segments = self.segments()?;
+
+        let mut lengths = Vec::with_capacity(segments.len() * ARC_LENGTH_SAMPLES + 1);
+        let mut total = 0.;
+        let mut previous = segments[0].at(0.);
+        lengths.push(0.);
+        for segment in &segments {
+            for i in 1..=ARC_LENGTH_SAMPLES {
+                let p = segment.at(i as f64 / ARC_LENGTH_SAMPLES as f64);
+                total += (p - previous).length();
+                lengths.push(total);
+                previous = p;
+            }
+        }
+
+        if total == 0. {
+            return Ok(previous);
+        }
+
+        let target = t.clamp(0., 1.) * total;
+        let i = lengths
+            .partition_point(|l| *l < target)
+            .clamp(1, lengths.len() - 1);
+        let f = (target - lengths[i - 1]) / (lengths[i] - lengths[i - 1]).max(f64::EPSILON);
+        let u = (i - 1) as f64 + f;
+
+        let segment = ((u as usize) / ARC_LENGTH_SAMPLES).min(segments.len() - 1);
+        let local_t = (u - (segment * ARC_LENGTH_SAMPLES) as f64) / ARC_LENGTH_SAMPLES as f64;
+        Ok(segments[segment].at(local_t))
+    }
+
+    fn segments(&self) -> Result<Vec<CubicBezier>, Box<dyn Error>> {
+        match self {
+            Spline {
+                catmull_rom: Some(points),
+                bezier: None,
+            } => {
+                if points.len() < 2 {
+                    return Err(Box::new(ModelError::new(
+                        "Catmull-Rom spline needs at least 2 points",
+                    )));
+                }
+                let p: Vec<Vec3> = points.iter().map(Vec3::from).collect();
+                let last = p.len() - 1;
+
+                // Converts each Catmull-Rom segment to the equivalent cubic bezier, with the
+                // end points repeated so that the spline passes through all points
+                Ok((0..last)
+                    .map(|i| {
+                        let p0 = p[i.saturating_sub(1)];
+                        let p1 = p[i];
+                        let p2 = p[i + 1];
+                        let p3 = p[(i + 2).min(last)];
+                        CubicBezier([p1, p1 + (p2 - p0) / 6., p2 - (p3 - p1) / 6., p2])
+                    })
+                    .collect())
+            }
+            Spline {
+                catmull_rom: None,
+                bezier: Some(points),
+            } => {
+                if points.len() < 4 || (points.len() - 1) % 3 != 0 {
+                    return Err(Box::new(ModelError::new(
+                        "Bezier spline needs 3n+1 points, each segment being start, two control points and end",
+                    )));
+                }
+                let p: Vec<Vec3> = points.iter().map(Vec3::from).collect();
+                Ok(p.windows(4)
+                    .step_by(3)
+                    .map(|w| CubicBezier([w[0], w[1], w[2], w[3]]))
+                    .collect())
+            }
+            _ => Err(Box::new(ModelError::new(
+                "Spline should have single field defined",
+            ))),
+        }
+    }
+}
+
+struct CubicBezier([Vec3; 4]);
+
+impl CubicBezier {
+    fn at(&self, t: f64) -> Vec3 {
+        let [p0, p1, p2, p3] = self.0;
+        let s = 1. - t;
+        p0 * (s * s * s) + p1 * (3. * s * s * t) + p2 * (3. * s * t * t) + p3 * (t * t * t)
+    }
+}
+
+impl HelpDocumentation for CameraPath {
+    fn get_documentation_structure(depth: u8) -> DocumentationStructure {
+        DocumentationStructure {
+            description: "Moves the camera along splines as the frame index increases. The camera moves with constant speed along the splines".to_string(),
+            fields: HashMap::from([
+                ("start_frame".to_string(), FieldInfo::new_simple(
+                    "Frame index where the camera is at the start of the path. Defaults to 0",
+                    Optional,
+                    "Frame index of the start of the path"
+                )),
+                ("end_frame".to_string(), FieldInfo::new_simple(
+                    "Frame index where the camera reaches the end of the path",
+                    Normal,
+                    "Frame index of the end of the path"
+                )),
+                ("look_from".to_string(), FieldInfo::new(
+                    "Spline the camera position follows. Replaces the look_from of the camera",
+                    Normal,
+                    Spline::get_documentation_structure(depth + 1)
+                )),
+                ("look_at".to_string(), FieldInfo::new(
+                    "Spline the position the camera is pointed at follows. Replaces the look_at of the camera",
+                    Optional,
+                    Spline::get_documentation_structure(depth + 1)
+                )),
+                ("vertical_fov_degrees".to_string(), FieldInfo::new_simple(
+                    "Field of view values spread evenly over the path, with linear interpolation in between",
+                    OptionalList,
+                    "Field of view in degrees"
+                )),
+                ("aperture_size".to_string(), FieldInfo::new_simple(
+                    "Aperture values spread evenly over the path, with linear interpolation in between",
+                    OptionalList,
+                    "The radius of the aperture"
+                )),
+            ]),
+        }
+    }
+}
+
+impl HelpDocumentation for Spline {
+    fn get_documentation_structure(depth: u8) -> DocumentationStructure {
+        DocumentationStructure {
+            description: "A smooth curve defined by a list of points".to_string(),
+            fields: HashMap::from([
+                (
+                    "catmull_rom".to_string(),
+                    FieldInfo::new(
+                        "A curve that passes through all of the given points",
+                        OptionalList,
+                        Pos::get_documentation_structure(depth + 1),
+                    ),
+                ),
+                (
+                    "bezier".to_string(),
+                    FieldInfo::new(
+                        "Connected cubic bezier curves. Given as start point, followed by two control points and an end point for each curve, where the end point is the start of the next curve",
+                        OptionalList,
+                        Pos::get_documentation_structure(depth + 1),
+                    ),
+                ),
+            ]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_near(expected: Vec3, actual: Vec3) {
+        assert!(
+            (expected - actual).length() < 1e-3,
+            "expected {expected} but got {actual}"
+        );
+    }
+
+    #[test]
+    fn catmull_rom_constant_speed() {
+        let spline = Spline {
+            catmull_rom: Some(vec![
+                Pos::new(0., 0., 0.),
+                Pos::new(2., 0., 0.),
+                Pos::new(10., 0., 0.),
+            ]),
+            bezier: None,
+        };
+
+        assert_near(Vec3::new(0., 0., 0.), spline.at(0.).unwrap());
+        assert_near(Vec3::new(1., 0., 0.), spline.at(0.1).unwrap());
+        assert_near(Vec3::new(2., 0., 0.), spline.at(0.2).unwrap());
+        assert_near(Vec3::new(5., 0., 0.), spline.at(0.5).unwrap());
+        assert_near(Vec3::new(10., 0., 0.), spline.at(1.).unwrap());
+    }
+
+    #[test]
+    fn bezier() {
+        let spline = Spline {
+            catmull_rom: None,
+            bezier: Some(vec![
+                Pos::new(0., 0., 0.),
+                Pos::new(0., 1., 0.),
+                Pos::new(1., 1., 0.),
+                Pos::new(1., 0., 0.),
+            ]),
+        };
+        assert_near(Vec3::new(0., 0., 0.), spline.at(0.).unwrap());
+        assert_near(Vec3::new(0.5, 0.75, 0.), spline.at(0.5).unwrap());
+        assert_near(Vec3::new(1., 0., 0.), spline.at(1.).unwrap());
+
+        let invalid = Spline {
+            catmull_rom: None,
+            bezier: Some(vec![Pos::new(0., 0., 0.), Pos::new(1., 0., 0.)]),
+        };
+        assert!(invalid.at(0.).is_err());
+    }
+
+    #[test]
+    fn path_at_frame() {
+        let path: CameraPath = serde_yaml::from_str(
+            "start_frame: 10
+end_frame: 20
+look_from:
+  catmull_rom:
+  - 0, 0, 0
+  - 0, 0, 10
+vertical_fov_degrees: [60, 40, 50]
+",
+        )
+        .unwrap();
+
+        let point = path.at_frame(0.).unwrap();
+        assert_eq!(Pos::new(0., 0., 0.), point.look_from);
+        assert_eq!(None, point.look_at);
+        assert_eq!(Some(60.), point.vertical_fov_degrees);
+        assert_eq!(None, point.aperture_size);
+
+        let point = path.at_frame(15.).unwrap();
+        assert_near(Vec3::new(0., 0., 5.), (&point.look_from).into());
+        assert_eq!(Some(40.), point.vertical_fov_degrees);
+
+        let point = path.at_frame(17.5).unwrap();
+        assert_eq!(Some(45.), point.vertical_fov_degrees);
+    }
+}
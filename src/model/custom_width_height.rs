@@ -6,17 +6,26 @@ use serde::{Deserialize, Serialize};
 use crate::model::FieldType::Normal;
 use crate::model::{
     Creator, CreatorContext, DocumentationStructure, FieldInfo, HelpDocumentation, ModelError,
+    deserialize_rounded,
 };
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 #[serde(deny_unknown_fields)]
 pub struct CustomWidthHeight {
+    #[serde(deserialize_with = "deserialize_rounded")]
     pub width: usize,
+    #[serde(deserialize_with = "deserialize_rounded")]
     pub height: usize,
 }
 
 impl Creator<(usize, usize)> for CustomWidthHeight {
     fn create(&self, _: &CreatorContext) -> Result<(usize, usize), Box<dyn Error>> {
+        self.size()
+    }
+}
+
+impl CustomWidthHeight {
+    pub fn size(&self) -> Result<(usize, usize), Box<dyn Error>> {
         if self.width < 1 || self.width > 8000 {
             return Err(From::from(ModelError::new(
                 "Width must be between than 1 and 8000",
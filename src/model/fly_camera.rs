@@ -0,0 +1,137 @@
+use solstrale::geo::vec3::Vec3;
+use std::f64::consts::FRAC_PI_2;
+
+/// First person camera that moves through the scene, looking around from its own position
+/// instead of orbiting a target
+#[derive(Clone, Debug, PartialEq)]
+pub struct FlyCamera {
+    pub current_position: Vec3,
+    pub current_yaw: f64,
+    pub current_pitch: f64,
+
+    pub target_position: Vec3,
+    pub target_yaw: f64,
+    pub target_pitch: f64,
+
+    pub damping_factor: f64,
+    /// Movement speed in scene units per second
+    pub speed: f64,
+    /// Distance to the point looked at, kept so that orbiting continues around a point in front
+    /// of the camera when switching back to the orbit camera
+    pub focus_distance: f64,
+}
+
+impl FlyCamera {
+    pub fn new(look_from: Vec3, look_at: Vec3, damping_factor: f64) -> Self {
+        let dir = look_at - look_from;
+        let distance = dir.length().max(0.01);
+        let yaw = dir.x.atan2(dir.z);
+        let pitch = (dir.y / distance).clamp(-1., 1.).asin();
+
+        Self {
+            current_position: look_from,
+            current_yaw: yaw,
+            current_pitch: pitch,
+            target_position: look_from,
+            target_yaw: yaw,
+            target_pitch: pitch,
+            damping_factor,
+            speed: distance / 2.,
+            focus_distance: distance,
+        }
+    }
+
+    pub fn update(&mut self) -> bool {
+        let mut changed = false;
+
+        if (self.current_position - self.target_position).length() > 0.001 {
+            self.current_position = self.current_position
+                + (self.target_position - self.current_position) * self.damping_factor;
+            changed = true;
+        } else {
+            self.current_position = self.target_position;
+        }
+
+        if (self.current_yaw - self.target_yaw).abs() > 0.001 {
+            self.current_yaw += (self.target_yaw - self.current_yaw) * self.damping_factor;
+            changed = true;
+        } else {
+            self.current_yaw = self.target_yaw;
+        }
+
+        if (self.current_pitch - self.target_pitch).abs() > 0.001 {
+            self.current_pitch += (self.target_pitch - self.current_pitch) * self.damping_factor;
+            changed = true;
+        } else {
+            self.current_pitch = self.target_pitch;
+        }
+
+        changed
+    }
+
+    pub fn look_from(&self) -> Vec3 {
+        self.current_position
+    }
+
+    pub fn look_at(&self) -> Vec3 {
+        self.current_position
+            + direction(self.current_yaw, self.current_pitch) * self.focus_distance
+    }
+
+    pub fn look_around(&mut self, delta_yaw: f64, delta_pitch: f64) {
+        self.target_yaw += delta_yaw;
+        self.target_pitch =
+            (self.target_pitch + delta_pitch).clamp(-FRAC_PI_2 + 0.01, FRAC_PI_2 - 0.01);
+    }
+
+    /// Moves the camera relative to where it is looking. Forward follows the view direction,
+    /// while up is always straight up
+    pub fn fly(&mut self, forward: f64, right: f64, up: f64) {
+        let forward_dir = direction(self.target_yaw, self.target_pitch);
+        let right_dir = Vec3::new(-self.target_yaw.cos(), 0., self.target_yaw.sin());
+
+        self.target_position = self.target_position
+            + forward_dir * forward
+            + right_dir * right
+            + Vec3::new(0., up, 0.);
+    }
+
+    pub fn change_speed(&mut self, factor: f64) {
+        self.speed = (self.speed * factor).max(0.001);
+    }
+}
+
+fn direction(yaw: f64, pitch: f64) -> Vec3 {
+    Vec3::new(
+        pitch.cos() * yaw.sin(),
+        pitch.sin(),
+        pitch.cos() * yaw.cos(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_near(expected: Vec3, actual: Vec3) {
+        assert!(
+            (expected - actual).length() < 1e-6,
+            "expected {expected} but got {actual}"
+        );
+    }
+
+    #[test]
+    fn fly() {
+        let mut camera = FlyCamera::new(Vec3::new(0., 0., -10.), Vec3::new(0., 0., 0.), 1.);
+        assert_near(Vec3::new(0., 0., -10.), camera.look_from());
+        assert_near(Vec3::new(0., 0., 0.), camera.look_at());
+
+        camera.fly(2., 1., 3.);
+        assert!(camera.update());
+        assert_near(Vec3::new(-1., 3., -8.), camera.look_from());
+
+        camera.look_around(FRAC_PI_2, 0.);
+        camera.update();
+        assert_near(Vec3::new(9., 3., -8.), camera.look_at());
+    }
+}
@@ -10,7 +10,13 @@ pub struct HalfScreenWidthHeight {}
 
 impl Creator<(usize, usize)> for HalfScreenWidthHeight {
     fn create(&self, ctx: &CreatorContext) -> Result<(usize, usize), Box<dyn Error>> {
-        Ok((ctx.screen_width / 2, ctx.screen_height / 2))
+        Ok(self.size(ctx.screen_width, ctx.screen_height))
+    }
+}
+
+impl HalfScreenWidthHeight {
+    pub fn size(&self, screen_width: usize, screen_height: usize) -> (usize, usize) {
+        (screen_width / 2, screen_height / 2)
     }
 }
 
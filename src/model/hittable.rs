@@ -2,11 +2,15 @@ use crate::model::FieldType::Optional;
 use crate::model::r#box::Box;
 use crate::model::obj_model::ObjModel;
 use crate::model::quad::Quad;
+use crate::model::ray_cast::{BoundingBox, RayCast};
 use crate::model::sphere::Sphere;
+use crate::model::transformation::{Axis, Transformation, rotate, scale, translate};
 use crate::model::{
     Creator, CreatorContext, DocumentationStructure, FieldInfo, HelpDocumentation, ModelError,
 };
 use serde::{Deserialize, Serialize};
+use solstrale::geo::Aabb;
+use solstrale::geo::vec3::Vec3;
 use solstrale::hittable::Hittables;
 use std::collections::HashMap;
 use std::error::Error;
@@ -58,6 +62,148 @@ impl Creator<Vec<Hittables>> for Hittable {
     }
 }
 
+/// Changes made by dragging the object in the viewport. Spheres change their center and radius,
+/// while other objects get transformations appended
+impl Hittable {
+    pub fn translate(&mut self, delta: Vec3) {
+        if let Some(sphere) = &mut self.sphere {
+            sphere.center = (Vec3::from(&sphere.center) + delta).into();
+        } else if let Some(transformations) = self.transformations_mut() {
+            translate(transformations, delta);
+        }
+    }
+
+    /// Spheres look the same when rotated, so they are left unchanged
+    pub fn rotate(&mut self, axis: Axis, degrees: f64, pivot: Vec3) {
+        if let Some(transformations) = self.transformations_mut() {
+            rotate(transformations, axis, degrees, pivot);
+        }
+    }
+
+    pub fn scale(&mut self, factor: f64, pivot: Vec3) {
+        if let Some(sphere) = &mut self.sphere {
+            sphere.radius *= factor;
+        } else if let Some(transformations) = self.transformations_mut() {
+            scale(transformations, factor, pivot);
+        }
+    }
+
+    /// The fields changed by translate, rotate and scale, as yaml values to write to the scene
+    pub fn transformed_fields(
+        &self,
+    ) -> Result<Vec<(&'static str, Option<String>)>, serde_yaml::Error> {
+        if let Some(sphere) = &self.sphere {
+            return Ok(vec![
+                (
+                    "center",
+                    Some(serde_yaml::to_string(&sphere.center)?.trim().to_string()),
+                ),
+                (
+                    "radius",
+                    Some(serde_yaml::to_string(&sphere.radius)?.trim().to_string()),
+                ),
+            ]);
+        }
+        let transformations = match self {
+            Hittable { model: Some(m), .. } => &m.transformations,
+            Hittable { quad: Some(q), .. } => &q.transformations,
+            Hittable { r#box: Some(b), .. } => &b.transformations,
+            _ => return Ok(vec![]),
+        };
+        let value = if transformations.is_empty() {
+            None
+        } else {
+            Some(
+                serde_yaml::to_string(transformations)?
+                    .trim_end()
+                    .to_string(),
+            )
+        };
+        Ok(vec![("transformations", value)])
+    }
+
+    fn transformations_mut(&mut self) -> Option<&mut Vec<Transformation>> {
+        match self {
+            Hittable { model: Some(m), .. } => Some(&mut m.transformations),
+            Hittable { quad: Some(q), .. } => Some(&mut q.transformations),
+            Hittable { r#box: Some(b), .. } => Some(&mut b.transformations),
+            _ => None,
+        }
+    }
+}
+
+impl RayCast for Hittable {
+    fn ray_cast(
+        &self,
+        origin: Vec3,
+        direction: Vec3,
+    ) -> Result<Option<f64>, std::boxed::Box<dyn Error>> {
+        match self {
+            Hittable {
+                sphere: Some(s),
+                model: None,
+                quad: None,
+                r#box: None,
+            } => s.ray_cast(origin, direction),
+            Hittable {
+                sphere: None,
+                model: Some(m),
+                quad: None,
+                r#box: None,
+            } => m.ray_cast(origin, direction),
+            Hittable {
+                sphere: None,
+                model: None,
+                quad: Some(q),
+                r#box: None,
+            } => q.ray_cast(origin, direction),
+            Hittable {
+                sphere: None,
+                model: None,
+                quad: None,
+                r#box: Some(b),
+            } => b.ray_cast(origin, direction),
+            _ => Err(From::from(ModelError::new(
+                "Hittable should have single field defined",
+            ))),
+        }
+    }
+}
+
+impl BoundingBox for Hittable {
+    fn bounding_box(&self) -> Result<Aabb, std::boxed::Box<dyn Error>> {
+        match self {
+            Hittable {
+                sphere: Some(s),
+                model: None,
+                quad: None,
+                r#box: None,
+            } => s.bounding_box(),
+            Hittable {
+                sphere: None,
+                model: Some(m),
+                quad: None,
+                r#box: None,
+            } => m.bounding_box(),
+            Hittable {
+                sphere: None,
+                model: None,
+                quad: Some(q),
+                r#box: None,
+            } => q.bounding_box(),
+            Hittable {
+                sphere: None,
+                model: None,
+                quad: None,
+                r#box: Some(b),
+            } => b.bounding_box(),
+            _ => Err(From::from(ModelError::new(
+                "Hittable should have single field defined",
+            ))),
+        }
+    }
+}
+
 impl HelpDocumentation for Hittable {
     fn get_documentation_structure(depth: u8) -> DocumentationStructure {
         DocumentationStructure {
@@ -99,3 +245,46 @@ impl HelpDocumentation for Hittable {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn transformed_fields() {
+        let mut hittable: Hittable = serde_yaml::from_str(
+            "quad:
+  q: 0, 0, 0
+  u: 1, 0, 0
+  v: 0, 1, 0
+",
+        )
+        .unwrap();
+        hittable.translate(Vec3::new(1., 2., 3.));
+        hittable.rotate(Axis::Y, 45., Vec3::new(1., 2., 3.));
+        assert_eq!(
+            vec![(
+                "transformations",
+                Some("- rotation_y: 45.0\n- translation: 1, 2, 3".to_string())
+            )],
+            hittable.transformed_fields().unwrap()
+        );
+
+        let mut hittable: Hittable = serde_yaml::from_str(
+            "sphere:
+  center: 0, 0, 0
+  radius: 1
+",
+        )
+        .unwrap();
+        hittable.translate(Vec3::new(0.5, 0., 0.));
+        hittable.scale(2., Vec3::new(0.5, 0., 0.));
+        assert_eq!(
+            vec![
+                ("center", Some("0.5, 0, 0".to_string())),
+                ("radius", Some("2.0".to_string()))
+            ],
+            hittable.transformed_fields().unwrap()
+        );
+    }
+}
@@ -0,0 +1,329 @@
+use std::error::Error;
+
+use serde::Deserialize;
+use serde_yaml::Value;
+
+use crate::model::ModelError;
+
+static KEYFRAMES: &str = "keyframes";
+
+/// A value at a given frame. The easing describes how the value changes from the previous
+/// keyframe to this one
+#[derive(Deserialize, PartialEq, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+struct Keyframe {
+    frame: f64,
+    value: Value,
+    #[serde(default)]
+    ease: Ease,
+}
+
+#[derive(Deserialize, PartialEq, Debug, Clone, Copy)]
+#[serde(untagged)]
+enum Ease {
+    Named(NamedEase),
+    /// Control points x1, y1, x2, y2 of a cubic bezier from 0, 0 to 1, 1. Same as the CSS
+    /// cubic-bezier timing function
+    Bezier {
+        bezier: [f64; 4],
+    },
+}
+
+#[derive(Deserialize, PartialEq, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum NamedEase {
+    Linear,
+    Step,
+    In,
+    Out,
+    InOut,
+}
+
+impl Default for Ease {
+    fn default() -> Self {
+        Ease::Named(NamedEase::Linear)
+    }
+}
+
+impl Ease {
+    fn apply(&self, t: f64) -> f64 {
+        match self {
+            Ease::Named(NamedEase::Linear) => t,
+            Ease::Named(NamedEase::Step) => 0.,
+            Ease::Named(NamedEase::In) => t * t * t,
+            Ease::Named(NamedEase::Out) => 1. - (1. - t).powi(3),
+            Ease::Named(NamedEase::InOut) => {
+                if t < 0.5 {
+                    4. * t * t * t
+                } else {
+                    1. - (-2. * t + 2.).powi(3) / 2.
+                }
+            }
+            Ease::Bezier {
+                bezier: [x1, y1, x2, y2],
+            } => cubic_bezier(*x1, *y1, *x2, *y2, t),
+        }
+    }
+}
+
+/// Replaces all `keyframes` values in the scene document with the value interpolated at
+/// the given frame. Returns whether any keyframes were found
+pub fn resolve_keyframes(document: &mut Value, frame: f64) -> Result<bool, Box<dyn Error>> {
+    match document {
+        Value::Mapping(mapping) => {
+            if mapping.len() == 1
+                && let Some(keyframes) = mapping.get(KEYFRAMES)
+            {
+                let keyframes: Vec<Keyframe> = serde_yaml::from_value(keyframes.clone())?;
+                *document = interpolate(&keyframes, frame)?;
+                return Ok(true);
+            }
+
+            let mut found = false;
+            for (_, value) in mapping.iter_mut() {
+                found |= resolve_keyframes(value, frame)?;
+            }
+            Ok(found)
+        }
+        Value::Sequence(sequence) => {
+            let mut found = false;
+            for value in sequence.iter_mut() {
+                found |= resolve_keyframes(value, frame)?;
+            }
+            Ok(found)
+        }
+        _ => Ok(false),
+    }
+}
+
+fn interpolate(keyframes: &[Keyframe], frame: f64) -> Result<Value, Box<dyn Error>> {
+    let (first, last) = match (keyframes.first(), keyframes.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return Err(Box::new(ModelError::new("Keyframes must not be empty"))),
+    };
+    if keyframes.windows(2).any(|w| w[0].frame >= w[1].frame) {
+        return Err(Box::new(ModelError::new(
+            "Keyframes must be sorted by increasing frame",
+        )));
+    }
+
+    if frame <= first.frame {
+        return Ok(first.value.clone());
+    }
+    if frame >= last.frame {
+        return Ok(last.value.clone());
+    }
+
+    let i = keyframes.iter().position(|k| k.frame > frame).unwrap_or(0);
+    let (from, to) = (&keyframes[i - 1], &keyframes[i]);
+    let t = to
+        .ease
+        .apply((frame - from.frame) / (to.frame - from.frame));
+
+    let a = value_to_components(&from.value)?;
+    let b = value_to_components(&to.value)?;
+    if a.len() != b.len() {
+        return Err(Box::new(ModelError::new(&format!(
+            "Keyframe values {} and {} have different number of components",
+            value_to_string(&from.value),
+            value_to_string(&to.value)
+        ))));
+    }
+
+    let components: Vec<f64> = a.iter().zip(b).map(|(a, b)| a + (b - a) * t).collect();
+    Ok(match &from.value {
+        Value::String(_) => Value::String(
+            components
+                .iter()
+                .map(|c| c.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        _ => Value::from(components[0]),
+    })
+}
+
+/// Numbers have a single component while positions and colors are strings like "1, 2, 3"
+fn value_to_components(value: &Value) -> Result<Vec<f64>, ModelError> {
+    let err = || {
+        ModelError::new(&format!(
+            "Keyframe value {} must be a number or comma separated numbers like 1, 2, 3",
+            value_to_string(value)
+        ))
+    };
+
+    match value {
+        Value::Number(n) => n.as_f64().map(|n| vec![n]).ok_or_else(err),
+        Value::String(s) => s
+            .split(',')
+            .map(|c| c.trim().parse::<f64>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| err()),
+        _ => Err(err()),
+    }
+}
+
+fn value_to_string(value: &Value) -> String {
+    serde_yaml::to_string(value)
+        .map(|s| s.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Finds y for the given x on the bezier curve by solving for the curve parameter with
+/// bisection, which always converges since x is monotonic for control x values in 0..1
+fn cubic_bezier(x1: f64, y1: f64, x2: f64, y2: f64, x: f64) -> f64 {
+    let bezier = |a: f64, b: f64, s: f64| {
+        3. * (1. - s) * (1. - s) * s * a + 3. * (1. - s) * s * s * b + s * s * s
+    };
+    let x1 = x1.clamp(0., 1.);
+    let x2 = x2.clamp(0., 1.);
+
+    let (mut low, mut high) = (0., 1.);
+    let mut s = x;
+    for _ in 0..50 {
+        s = (low + high) / 2.;
+        if bezier(x1, x2, s) < x {
+            low = s;
+        } else {
+            high = s;
+        }
+    }
+    bezier(y1, y2, s)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::render_config::RenderConfig;
+
+    fn resolve(yaml: &str, frame: f64) -> Value {
+        let mut document: Value = serde_yaml::from_str(yaml).unwrap();
+        resolve_keyframes(&mut document, frame).unwrap();
+        document
+    }
+
+    #[test]
+    fn linear() {
+        let yaml = "radius:
+  keyframes:
+  - frame: 0
+    value: 1
+  - frame: 10
+    value: 3
+";
+        assert_eq!(Value::from(1), resolve(yaml, -5.)["radius"]);
+        assert_eq!(Value::from(2.), resolve(yaml, 5.)["radius"]);
+        assert_eq!(Value::from(3), resolve(yaml, 15.)["radius"]);
+
+        // Numbers written without a decimal point still animate smoothly
+        let yaml = "radius:
+  keyframes:
+  - frame: 0
+    value: 1
+  - frame: 50
+    value: 3
+";
+        assert_eq!(Value::from(2.), resolve(yaml, 25.)["radius"]);
+        assert_eq!(Value::from(1.04), resolve(yaml, 1.)["radius"]);
+    }
+
+    #[test]
+    fn integers() {
+        let yaml = "render_configuration:
+  samples_per_pixel:
+    keyframes:
+    - frame: 0
+      value: 10
+    - frame: 3
+      value: 20
+";
+        // Integer fields round the interpolated value when they are read
+        let document = resolve(yaml, 1.);
+        let config: RenderConfig =
+            serde_yaml::from_value(document["render_configuration"].clone()).unwrap();
+        assert_eq!(Some(13), config.samples_per_pixel);
+    }
+
+    #[test]
+    fn positions() {
+        let yaml = "world:
+- sphere:
+    center:
+      keyframes:
+      - frame: 0
+        value: 0, 0, 0
+      - frame: 4
+        value: 4, 2, -2
+      - frame: 8
+        value: 0, 0, 0
+        ease: step
+";
+        assert_eq!(
+            Value::from("1, 0.5, -0.5"),
+            resolve(yaml, 1.)["world"][0]["sphere"]["center"]
+        );
+        assert_eq!(
+            Value::from("4, 2, -2"),
+            resolve(yaml, 7.)["world"][0]["sphere"]["center"]
+        );
+        assert_eq!(
+            Value::from("0, 0, 0"),
+            resolve(yaml, 8.)["world"][0]["sphere"]["center"]
+        );
+    }
+
+    #[test]
+    fn easing() {
+        assert_eq!(0.125, Ease::Named(NamedEase::In).apply(0.5));
+        assert_eq!(0.875, Ease::Named(NamedEase::Out).apply(0.5));
+        assert_eq!(0.5, Ease::Named(NamedEase::InOut).apply(0.5));
+        let linear_bezier = Ease::Bezier {
+            bezier: [0., 0., 1., 1.],
+        };
+        assert!((linear_bezier.apply(0.3) - 0.3).abs() < 1e-9);
+        let ease_in_bezier = Ease::Bezier {
+            bezier: [0.42, 0., 1., 1.],
+        };
+        assert!(ease_in_bezier.apply(0.3) < 0.3);
+
+        let ease: Ease = serde_yaml::from_str("bezier: [0.42, 0, 0.58, 1]").unwrap();
+        assert_eq!(
+            Ease::Bezier {
+                bezier: [0.42, 0., 0.58, 1.]
+            },
+            ease
+        );
+        let ease: Ease = serde_yaml::from_str("in_out").unwrap();
+        assert_eq!(Ease::Named(NamedEase::InOut), ease);
+    }
+
+    #[test]
+    fn errors() {
+        let mut document: Value = serde_yaml::from_str(
+            "radius:
+  keyframes:
+  - frame: 5
+    value: 1
+  - frame: 0
+    value: 3
+",
+        )
+        .unwrap();
+        assert!(resolve_keyframes(&mut document, 1.).is_err());
+
+        let mut document: Value = serde_yaml::from_str(
+            "center:
+  keyframes:
+  - frame: 0
+    value: 1, 2
+  - frame: 1
+    value: 1, 2, 3
+",
+        )
+        .unwrap();
+        assert!(resolve_keyframes(&mut document, 0.5).is_err());
+
+        let mut document: Value = serde_yaml::from_str("radius: { keyframes: [] }").unwrap();
+        assert!(resolve_keyframes(&mut document, 0.).is_err());
+    }
+}
@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::FieldType::{Normal, Optional};
+use crate::model::{DocumentationStructure, FieldInfo, HelpDocumentation, ModelError};
+
+/// Height of a full frame 35mm sensor
+const DEFAULT_SENSOR_HEIGHT: f64 = 24.;
+/// Scene units are meters by default
+const DEFAULT_SCENE_UNIT_MM: f64 = 1000.;
+
+/// Physical camera parameters, converted to the field of view and aperture used by the renderer
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct Lens {
+    pub focal_length: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sensor_height: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub f_stop: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scene_unit_mm: Option<f64>,
+}
+
+impl Lens {
+    pub fn vertical_fov_degrees(&self) -> Result<f64, Box<dyn Error>> {
+        if self.focal_length <= 0. {
+            return Err(Box::new(ModelError::new(
+                "Lens focal_length must be greater than 0",
+            )));
+        }
+        let sensor_height = self.sensor_height.unwrap_or(DEFAULT_SENSOR_HEIGHT);
+        Ok((2. * (sensor_height / (2. * self.focal_length)).atan()).to_degrees())
+    }
+
+    /// Diameter of the aperture in scene units, which is the focal length divided by the f-stop
+    pub fn aperture_size(&self) -> Result<Option<f64>, Box<dyn Error>> {
+        let Some(f_stop) = self.f_stop else {
+            return Ok(None);
+        };
+        if f_stop <= 0. {
+            return Err(Box::new(ModelError::new(
+                "Lens f_stop must be greater than 0",
+            )));
+        }
+        let scene_unit_mm = self.scene_unit_mm.unwrap_or(DEFAULT_SCENE_UNIT_MM);
+        Ok(Some(self.focal_length / f_stop / scene_unit_mm))
+    }
+}
+
+impl HelpDocumentation for Lens {
+    fn get_documentation_structure(_: u8) -> DocumentationStructure {
+        DocumentationStructure {
+            description: "Describes the camera as a physical lens and sensor. Replaces vertical_fov_degrees, and aperture_size if f_stop is given".to_string(),
+            fields: HashMap::from([
+                ("focal_length".to_string(), FieldInfo::new_simple(
+                    "Focal length of the lens in millimeters. A longer focal length gives a narrower field of view",
+                    Normal,
+                    "Focal length in millimeters"
+                )),
+                ("sensor_height".to_string(), FieldInfo::new_simple(
+                    "Height of the camera sensor in millimeters. Defaults to 24, a full frame sensor",
+                    Optional,
+                    "Sensor height in millimeters"
+                )),
+                ("f_stop".to_string(), FieldInfo::new_simple(
+                    "The focal length divided by the diameter of the aperture. A lower value gives a more shallow depth of field. Without it the whole scene is in focus",
+                    Optional,
+                    "F-number, for example 2.8"
+                )),
+                ("scene_unit_mm".to_string(), FieldInfo::new_simple(
+                    "Size of one scene unit in millimeters, used to convert the aperture to scene units. Defaults to 1000, a scene modeled in meters",
+                    Optional,
+                    "Millimeters per scene unit"
+                )),
+            ]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn conversion() {
+        let lens = Lens {
+            focal_length: 50.,
+            sensor_height: None,
+            f_stop: Some(2.),
+            scene_unit_mm: None,
+        };
+        assert!((lens.vertical_fov_degrees().unwrap() - 26.9915).abs() < 1e-4);
+        assert_eq!(Some(0.025), lens.aperture_size().unwrap());
+
+        let lens = Lens {
+            focal_length: 12.,
+            sensor_height: Some(24.),
+            f_stop: None,
+            scene_unit_mm: Some(10.),
+        };
+        assert!((lens.vertical_fov_degrees().unwrap() - 90.).abs() < 1e-9);
+        assert_eq!(None, lens.aperture_size().unwrap());
+    }
+}
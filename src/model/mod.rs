@@ -3,21 +3,29 @@ use std::error::Error;
 
 use derive_more::Display;
 use eframe::wgpu;
+use serde::Deserialize;
 
+use crate::model::keyframes::resolve_keyframes;
 use crate::model::pos::Pos;
 use crate::model::scene::Scene;
+use crate::model::scene_override::{SceneOverride, TemplateVariable, apply_overrides};
 use crate::model::template::apply_template;
+use crate::model::variant::apply_variant;
 
 mod blend;
 mod bloom_post_processor;
 mod r#box;
-mod camera_config;
-mod custom_width_height;
+pub mod camera_config;
+mod camera_path;
+pub mod custom_width_height;
+pub mod fly_camera;
 mod glass;
 mod half_screen_width_height;
 mod hittable;
 mod image;
+mod keyframes;
 mod lambertian;
+mod lens;
 mod light;
 mod material;
 mod metal;
@@ -29,19 +37,22 @@ mod pos;
 mod post_processor;
 mod quad;
 mod quarter_screen_width_height;
+pub mod ray_cast;
 mod render_config;
 mod rgb;
 mod saturation_post_processor;
 pub mod scene;
+pub mod scene_override;
 mod screen_width_height;
 mod sphere;
 mod template;
 mod texture;
-mod transformation;
-mod width_height;
+pub mod transformation;
+mod variant;
+pub mod width_height;
 
 #[derive(Clone, Debug, Display)]
-struct ModelError {
+pub struct ModelError {
     message: String,
 }
 
@@ -68,6 +79,42 @@ pub struct CreatorContext<'a> {
     pub queue: &'a wgpu::Queue,
 }
 
+/// Values made available as variables to the Tera templates of the scene
+#[derive(Clone, Debug)]
+pub struct TemplateContext {
+    pub frame_index: usize,
+    pub frame_count: usize,
+    pub fps: f64,
+    /// Size of the rendered image. Given as the size of the screen, which the `width_height` of
+    /// the scene is resolved against when it is parsed
+    pub width: usize,
+    pub height: usize,
+    pub mode: RenderMode,
+    pub variables: Vec<TemplateVariable>,
+}
+
+impl Default for TemplateContext {
+    fn default() -> Self {
+        TemplateContext {
+            frame_index: 0,
+            frame_count: 1,
+            fps: 30.,
+            width: 0,
+            height: 0,
+            mode: RenderMode::Interactive,
+            variables: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Display)]
+pub enum RenderMode {
+    #[display("interactive")]
+    Interactive,
+    #[display("batch")]
+    Batch,
+}
+
 pub trait Creator<T> {
     fn create(&self, ctx: &CreatorContext) -> Result<T, Box<dyn Error>>;
 }
@@ -151,9 +198,69 @@ pub fn get_documentation_structure_by_yaml_path(
     }
 }
 
-pub fn parse_scene_yaml(templated_yaml: &str, frame_index: usize) -> Result<Scene, Box<dyn Error>> {
-    let yaml = apply_template(templated_yaml, frame_index)?;
-    let scene: Scene = serde_yaml::from_str(&yaml)?;
+pub fn parse_scene_yaml(
+    templated_yaml: &str,
+    template_ctx: &TemplateContext,
+) -> Result<Scene, Box<dyn Error>> {
+    parse_scene_yaml_with_overrides(templated_yaml, template_ctx, &[], None)
+}
+
+/// Parses the scene with the size of the rendered image in the template context. A scene
+/// rendered at another size than the screen is parsed again with the size it is rendered at
+pub fn parse_scene_yaml_with_overrides(
+    templated_yaml: &str,
+    template_ctx: &TemplateContext,
+    overrides: &[SceneOverride],
+    variant: Option<&str>,
+) -> Result<Scene, Box<dyn Error>> {
+    let scene = parse_scene(templated_yaml, template_ctx, overrides, variant)?;
+    let (width, height) = scene
+        .render_configuration
+        .as_ref()
+        .and_then(|c| c.width_height.clone())
+        .unwrap_or_default()
+        .size(template_ctx.width, template_ctx.height)?;
+    if (width, height) == (template_ctx.width, template_ctx.height) {
+        return Ok(scene);
+    }
+
+    let template_ctx = TemplateContext {
+        width,
+        height,
+        ..template_ctx.clone()
+    };
+    parse_scene(templated_yaml, &template_ctx, overrides, variant)
+}
+
+fn parse_scene(
+    templated_yaml: &str,
+    template_ctx: &TemplateContext,
+    overrides: &[SceneOverride],
+    variant: Option<&str>,
+) -> Result<Scene, Box<dyn Error>> {
+    let yaml = apply_template(templated_yaml, template_ctx)?;
+
+    let mut document: serde_yaml::Value = serde_yaml::from_str(&yaml)?;
+    let mut modified = false;
+    if let Some(name) = variant {
+        apply_variant(&mut document, name)?;
+        modified = true;
+    }
+    modified |= resolve_keyframes(&mut document, template_ctx.frame_index as f64)?;
+    if !overrides.is_empty() {
+        apply_overrides(&mut document, overrides)?;
+        modified = true;
+    }
+
+    // Deserializing straight from the text keeps line numbers in parse errors
+    let mut scene: Scene = if modified {
+        serde_yaml::from_value(document)?
+    } else {
+        serde_yaml::from_str(&yaml)?
+    };
+    scene.camera = scene
+        .active_camera()?
+        .at_frame(template_ctx.frame_index as f64)?;
     Ok(scene)
 }
 
@@ -167,6 +274,36 @@ where
         .map_err(serde::de::Error::custom)
 }
 
+/// Reads an integer field that may also be given as a float, like the values interpolated
+/// between keyframes, which is rounded to the closest integer
+pub fn deserialize_rounded<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::de::Deserializer<'de>,
+    T: TryFrom<i64>,
+{
+    let value = f64::deserialize(deserializer)?;
+    let rounded = value.round();
+    if !rounded.is_finite() || rounded < i64::MIN as f64 || rounded > i64::MAX as f64 {
+        return Err(serde::de::Error::custom(format!(
+            "{value} is not a valid integer"
+        )));
+    }
+    T::try_from(rounded as i64)
+        .map_err(|_| serde::de::Error::custom(format!("{value} is out of range")))
+}
+
+/// Same as [deserialize_rounded] for optional fields, which also need `#[serde(default)]`
+pub fn deserialize_rounded_option<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::de::Deserializer<'de>,
+    T: TryFrom<i64>,
+{
+    #[derive(Deserialize)]
+    struct Rounded<T: TryFrom<i64>>(#[serde(deserialize_with = "deserialize_rounded")] T);
+
+    Ok(Option::<Rounded<T>>::deserialize(deserializer)?.map(|r| r.0))
+}
+
 #[cfg(test)]
 mod test {
     use crate::model::blend::Blend;
@@ -245,6 +382,8 @@ mod test {
             camera: CameraConfig {
                 vertical_fov_degrees: Some(0.0),
                 aperture_size: Some(0.0),
+                focus_distance: None,
+                lens: None,
                 look_from: Pos {
                     x: 0.0,
                     y: 0.0,
@@ -260,6 +399,8 @@ mod test {
                     y: 1.0,
                     z: 0.0,
                 }),
+                roll_degrees: None,
+                path: None,
             },
             background_color: Some(Rgb {
                 r: 0.0,
@@ -287,6 +428,9 @@ mod test {
                 }],
                 preview_interval_ms: Some(1000),
             }),
+            variants: Default::default(),
+            cameras: Default::default(),
+            active_camera: None,
         };
 
         let yaml = serde_yaml::to_string(&scene).unwrap();
@@ -334,4 +478,35 @@ world:
         let de_scene: Scene = serde_yaml::from_str(&yaml).unwrap();
         assert_eq!(scene, de_scene);
     }
+
+    #[test]
+    fn template_size() {
+        let yaml = "render_configuration:
+  width_height:
+    half_screen: {}
+  samples_per_pixel: {{ width + height }}
+camera:
+  look_from: 0, 0, 0
+  look_at: 0, 0, -1
+world: []
+";
+        let ctx = TemplateContext {
+            width: 800,
+            height: 600,
+            ..Default::default()
+        };
+        // The template sees the size of the rendered image, not of the screen
+        let scene = parse_scene_yaml(yaml, &ctx).unwrap();
+        assert_eq!(
+            Some(700),
+            scene.render_configuration.unwrap().samples_per_pixel
+        );
+
+        let yaml = yaml.replace("half_screen: {}", "screen: {}");
+        let scene = parse_scene_yaml(&yaml, &ctx).unwrap();
+        assert_eq!(
+            Some(1400),
+            scene.render_configuration.unwrap().samples_per_pixel
+        );
+    }
 }
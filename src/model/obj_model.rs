@@ -1,15 +1,20 @@
 use crate::model::FieldType::{List, Normal, Optional};
 use crate::model::material::Material;
-use crate::model::transformation::{Transformation, create_transformation};
+use crate::model::ray_cast::{BoundingBox, RayCast, aabb_from_points, box_corners, ray_cast_aabb};
+use crate::model::transformation::{Transformation, create_transformation, transformer};
 use crate::model::{
     Creator, CreatorContext, DocumentationStructure, FieldInfo, HelpDocumentation, ModelError,
 };
 use moka::sync::Cache;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
-use solstrale::hittable::Hittables;
+use solstrale::geo::Aabb;
+use solstrale::geo::transformation::{NopTransformer, Transformer};
+use solstrale::geo::vec3::Vec3;
+use solstrale::hittable::{Hittable, Hittables};
 use solstrale::loader::Loader;
 use solstrale::loader::obj::Obj;
+use solstrale::material::Materials;
 use solstrale::material::texture::SolidColor;
 use std::collections::HashMap;
 use std::error::Error;
@@ -17,6 +22,11 @@ use std::error::Error;
 static MODEL_CACHE: Lazy<Cache<String, Result<Hittables, ModelError>>> =
     Lazy::new(|| Cache::new(4));
 
+/// Bounds of the untransformed models by path and name, so that picking and dragging objects
+/// neither loads the models again nor pushes the rendered models out of the model cache
+static BOUNDS_CACHE: Lazy<Cache<(String, String), Result<Aabb, ModelError>>> =
+    Lazy::new(|| Cache::new(64));
+
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 #[serde(deny_unknown_fields)]
 pub struct ObjModel {
@@ -30,19 +40,25 @@ pub struct ObjModel {
 
 impl Creator<Hittables> for ObjModel {
     fn create(&self, ctx: &CreatorContext) -> Result<Hittables, Box<dyn Error>> {
-        let material = self.material.as_ref().map_or(
-            Ok(
-                solstrale::material::Lambertian::new(SolidColor::new(1., 1., 1.).into(), None)
-                    .into(),
-            ),
-            |m| m.create(ctx),
-        )?;
+        let material = self
+            .material
+            .as_ref()
+            .map_or(Ok(default_material()), |m| m.create(ctx))?;
         let transformation = create_transformation(&self.transformations, ctx)?;
+        self.load(&transformation, material)
+    }
+}
 
+impl ObjModel {
+    fn load(
+        &self,
+        transformation: &dyn Transformer,
+        material: Materials,
+    ) -> Result<Hittables, Box<dyn Error>> {
         let key = format!("{:?}", self);
         let model_result = MODEL_CACHE.get_with(key.to_owned(), || {
             Obj::new(&self.path, &self.name)
-                .load(&transformation, Some(material))
+                .load(transformation, Some(material))
                 .map_err(ModelError::new_from_err)
                 .map(|m| m.into())
         });
@@ -57,6 +73,49 @@ impl Creator<Hittables> for ObjModel {
     }
 }
 
+/// Only the bounding box of the model is hit, as the triangles are not available outside of
+/// the renderer
+impl RayCast for ObjModel {
+    fn ray_cast(&self, origin: Vec3, direction: Vec3) -> Result<Option<f64>, Box<dyn Error>> {
+        Ok(ray_cast_aabb(
+            origin,
+            direction,
+            &BoundingBox::bounding_box(self)?,
+        ))
+    }
+}
+
+/// The box around the untransformed model is transformed, which contains the transformed model.
+/// The model is loaded without its material, as that needs the render device
+impl BoundingBox for ObjModel {
+    fn bounding_box(&self) -> Result<Aabb, Box<dyn Error>> {
+        let key = (self.path.clone(), self.name.clone());
+        let bounds = BOUNDS_CACHE.get_with(key.clone(), || {
+            Obj::new(&self.path, &self.name)
+                .load(&NopTransformer(), Some(default_material()))
+                .map_err(ModelError::new_from_err)
+                .map(|m| m.bounding_box().clone())
+        });
+
+        let bounds = match bounds {
+            Ok(bounds) => bounds,
+            Err(err) => {
+                BOUNDS_CACHE.remove(&key);
+                return Err(Box::new(err));
+            }
+        };
+        Ok(aabb_from_points(&box_corners(
+            Vec3::new(bounds.x.min, bounds.y.min, bounds.z.min),
+            Vec3::new(bounds.x.max, bounds.y.max, bounds.z.max),
+            &transformer(&self.transformations)?,
+        )))
+    }
+}
+
+fn default_material() -> Materials {
+    solstrale::material::Lambertian::new(SolidColor::new(1., 1., 1.).into(), None).into()
+}
+
 impl HelpDocumentation for ObjModel {
     fn get_documentation_structure(depth: u8) -> DocumentationStructure {
         DocumentationStructure {
@@ -86,3 +145,41 @@ impl HelpDocumentation for ObjModel {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::Pos;
+
+    #[test]
+    fn transformed_bounding_box() {
+        let dir = std::env::temp_dir().join("solstrale-desktop-obj-bounds");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("triangle.obj"),
+            "v 0 0 0\nv 1 0 0\nv 0 2 0\nf 1 2 3\n",
+        )
+        .unwrap();
+
+        let mut model = ObjModel {
+            path: format!("{}/", dir.display()),
+            name: "triangle.obj".to_string(),
+            material: None,
+            transformations: vec![],
+        };
+        let bounds = model.bounding_box().unwrap();
+        assert_eq!((0., 1.), (bounds.x.min, bounds.x.max));
+        assert_eq!((0., 2.), (bounds.y.min, bounds.y.max));
+
+        // Moving the model reuses the cached bounds
+        model.transformations = vec![Transformation {
+            translation: Some(Pos::new(1., 2., 3.)),
+            ..Default::default()
+        }];
+        std::fs::remove_file(dir.join("triangle.obj")).unwrap();
+        let bounds = model.bounding_box().unwrap();
+        assert_eq!((1., 2.), (bounds.x.min, bounds.x.max));
+        assert_eq!((2., 4.), (bounds.y.min, bounds.y.max));
+        assert!((bounds.z.min - 3.).abs() < 0.01);
+    }
+}
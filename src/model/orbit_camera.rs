@@ -1,24 +1,80 @@
 use crate::model::{Creator, CreatorContext};
 use solstrale::camera::CameraConfig;
+use solstrale::geo::Aabb;
 use solstrale::geo::vec3::Vec3;
+use std::error::Error;
 use std::f64::consts::PI;
 
+/// Camera orbiting around a target. The azimuth is the angle around the up vector of the
+/// scene, and the polar angle is the angle from it, so scenes with any up vector orbit around
+/// their vertical axis
 #[derive(Clone, Debug, PartialEq)]
 pub struct OrbitCamera {
     pub current_target: Vec3,
     pub current_distance: f64,
     pub current_azimuth: f64,
     pub current_polar: f64,
+    pub current_roll: f64,
 
     pub target_target: Vec3,
     pub target_distance: f64,
     pub target_azimuth: f64,
     pub target_polar: f64,
+    /// Rotation around the view direction, positive tilts the camera to the right
+    pub target_roll: f64,
 
     pub damping_factor: f64,
     pub vertical_fov_degrees: f64,
     pub aperture_size: f64,
+    /// Up vector of the scene, which is orbited around. The camera itself is tilted from it by
+    /// the roll, see [`OrbitCamera::camera_up`]
     pub up: Vec3,
+
+    /// Size of the part of the scene being looked at, which zooming and panning are relative to
+    pub scene_size: f64,
+    /// Moving smoothly to a framed object, until reaching it or the user moves the camera
+    framing: bool,
+}
+
+/// Damping used when moving to a framed object, so the move is animated
+const FRAMING_DAMPING: f64 = 0.2;
+
+/// Closest the camera gets to looking straight down or up, where the up direction is undefined
+const MIN_POLAR: f64 = 0.01;
+
+/// Sides the camera can be snapped to look at the target from
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum View {
+    Front,
+    Back,
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+impl View {
+    pub const ALL: [View; 6] = [
+        View::Front,
+        View::Back,
+        View::Left,
+        View::Right,
+        View::Top,
+        View::Bottom,
+    ];
+
+    /// Azimuth and polar angle of the camera. For scenes with y up, front is looking along the
+    /// negative z axis, and for scenes with z up along the positive y axis
+    fn angles(self) -> (f64, f64) {
+        match self {
+            View::Front => (0., PI / 2.),
+            View::Back => (PI, PI / 2.),
+            View::Left => (-PI / 2., PI / 2.),
+            View::Right => (PI / 2., PI / 2.),
+            View::Top => (0., 0.),
+            View::Bottom => (0., PI),
+        }
+    }
 }
 
 impl OrbitCamera {
@@ -26,36 +82,92 @@ impl OrbitCamera {
         cc: &crate::model::camera_config::CameraConfig,
         ctx: &CreatorContext,
         damping_factor: f64,
-    ) -> Self {
-        let camera_config = cc.create(ctx).unwrap();
-
-        let dir = camera_config.look_from - camera_config.look_at;
-        let distance = dir.length();
-        let azimuth = dir.x.atan2(dir.z);
-        let polar = (dir.y / distance).acos();
+    ) -> Result<Self, Box<dyn Error>> {
+        // The camera orbits around the up vector before it is rolled
+        let camera_config = crate::model::camera_config::CameraConfig {
+            roll_degrees: None,
+            ..cc.clone()
+        }
+        .create(ctx)?;
+        let roll = cc.roll_degrees.unwrap_or(0.).to_radians();
+        if camera_config.up.length_squared() == 0. {
+            return Err("Camera up vector should not be zero".into());
+        }
+        if (camera_config.look_from - camera_config.look_at).length_squared() == 0. {
+            return Err("Camera look_from and look_at should not be the same position".into());
+        }
 
-        Self {
+        let mut camera = Self {
             current_target: camera_config.look_at,
-            current_distance: distance,
-            current_azimuth: azimuth,
-            current_polar: polar,
+            current_distance: 0.,
+            current_azimuth: 0.,
+            current_polar: 0.,
+            current_roll: roll,
             target_target: camera_config.look_at,
-            target_distance: distance,
-            target_azimuth: azimuth,
-            target_polar: polar,
+            target_distance: 0.,
+            target_azimuth: 0.,
+            target_polar: 0.,
+            target_roll: roll,
             damping_factor,
             vertical_fov_degrees: camera_config.vertical_fov_degrees,
             aperture_size: camera_config.aperture_size,
-            up: camera_config.up,
-        }
+            up: normalize(camera_config.up),
+            scene_size: 1.,
+            framing: false,
+        };
+        camera.set_view(camera_config.look_from, camera_config.look_at);
+        Ok(camera)
+    }
+
+    /// Moves the camera to the given view right away, without damping
+    pub fn set_view(&mut self, look_from: Vec3, look_at: Vec3) {
+        let (x, up, z) = basis(self.up);
+        let dir = look_from - look_at;
+        let distance = dir.length();
+        let azimuth = dir.dot(x).atan2(dir.dot(z));
+        let polar = (dir.dot(up) / distance).clamp(-1., 1.).acos();
+        self.set_orbit(look_at, distance, azimuth, polar);
+    }
+
+    /// Moves the camera to the given orbit right away, without damping
+    pub fn set_orbit(&mut self, target: Vec3, distance: f64, azimuth: f64, polar: f64) {
+        self.current_target = target;
+        self.current_distance = distance;
+        self.current_azimuth = azimuth;
+        self.current_polar = polar;
+        self.target_target = target;
+        self.target_distance = distance;
+        self.target_azimuth = azimuth;
+        self.target_polar = polar;
+    }
+
+    /// Rolls the camera right away, without damping
+    pub fn set_roll(&mut self, roll: f64) {
+        self.current_roll = roll;
+        self.target_roll = roll;
+    }
+
+    /// Orbits to look at the target from the side of the given view, taking the shortest way
+    /// around
+    pub fn snap_to(&mut self, view: View) {
+        let (azimuth, polar) = view.angles();
+        let turn = (azimuth - self.target_azimuth + PI).rem_euclid(2. * PI) - PI;
+        self.target_azimuth += turn;
+        self.target_polar = polar.clamp(MIN_POLAR, PI - MIN_POLAR);
+        self.target_roll = 0.;
     }
 
     pub fn update(&mut self) -> bool {
         let mut changed = false;
+        let damping_factor = if self.framing {
+            self.damping_factor.min(FRAMING_DAMPING)
+        } else {
+            self.damping_factor
+        };
 
         if (self.current_target - self.target_target).length() > 0.001 {
-            self.current_target = self.current_target
-                + (self.target_target - self.current_target) * self.damping_factor;
+            self.current_target =
+                self.current_target + (self.target_target - self.current_target) * damping_factor;
             changed = true;
         } else {
             self.current_target = self.target_target;
@@ -63,61 +175,180 @@ impl OrbitCamera {
 
         if (self.current_distance - self.target_distance).abs() > 0.001 {
             self.current_distance +=
-                (self.target_distance - self.current_distance) * self.damping_factor;
+                (self.target_distance - self.current_distance) * damping_factor;
             changed = true;
         } else {
             self.current_distance = self.target_distance;
         }
 
         if (self.current_azimuth - self.target_azimuth).abs() > 0.001 {
-            self.current_azimuth +=
-                (self.target_azimuth - self.current_azimuth) * self.damping_factor;
+            self.current_azimuth += (self.target_azimuth - self.current_azimuth) * damping_factor;
             changed = true;
         } else {
             self.current_azimuth = self.target_azimuth;
         }
 
         if (self.current_polar - self.target_polar).abs() > 0.001 {
-            self.current_polar += (self.target_polar - self.current_polar) * self.damping_factor;
+            self.current_polar += (self.target_polar - self.current_polar) * damping_factor;
             changed = true;
         } else {
             self.current_polar = self.target_polar;
         }
 
+        if (self.current_roll - self.target_roll).abs() > 0.001 {
+            self.current_roll += (self.target_roll - self.current_roll) * damping_factor;
+            changed = true;
+        } else {
+            self.current_roll = self.target_roll;
+        }
+
+        self.framing &= changed;
         changed
     }
 
     pub fn look_from(&self) -> Vec3 {
-        let x = self.current_distance * self.current_polar.sin() * self.current_azimuth.sin();
-        let y = self.current_distance * self.current_polar.cos();
-        let z = self.current_distance * self.current_polar.sin() * self.current_azimuth.cos();
+        self.position(
+            self.current_target,
+            self.current_distance,
+            self.current_azimuth,
+            self.current_polar,
+        )
+    }
+
+    /// Where the camera will be once it has reached its targets
+    pub fn target_look_from(&self) -> Vec3 {
+        self.position(
+            self.target_target,
+            self.target_distance,
+            self.target_azimuth,
+            self.target_polar,
+        )
+    }
+
+    /// Sets the size of the scene from its bounding box. A scene reaching far beyond the view,
+    /// like one standing on a huge ground sphere, is measured by the distance to the target
+    /// instead, so zoom steps stay in proportion to what is seen
+    pub fn set_scene_bounds(&mut self, bounds: &Aabb) {
+        let mut size = Vec3::new(bounds.x.size(), bounds.y.size(), bounds.z.size()).length();
+        if self.target_distance > 0. {
+            size = size.min(self.target_distance * 2.);
+        }
+        self.scene_size = if size > 0. { size } else { 1. };
+    }
+
+    /// Moves the camera towards the center of the box, at a distance where the whole box is in
+    /// view, keeping the direction it is looking at. Zooming and panning are relative to the
+    /// size of the box from then on
+    pub fn frame(&mut self, bounds: &Aabb, aspect_ratio: f64) {
+        let radius = (Vec3::new(bounds.x.size(), bounds.y.size(), bounds.z.size()).length() / 2.)
+            .max(self.scene_size * 0.001);
+        self.scene_size = radius * 2.;
+        let half_vertical = self.vertical_fov_degrees.to_radians() / 2.;
+        let half_horizontal = (half_vertical.tan() * aspect_ratio).atan();
 
-        self.current_target + Vec3::new(x, y, z)
+        self.target_target = bounds.center();
+        self.target_distance = radius / half_vertical.min(half_horizontal).sin();
+        self.framing = true;
     }
 
     pub fn look_at(&self) -> Vec3 {
         self.current_target
     }
 
+    /// Up vector of the camera, which is the up vector of the scene tilted by the roll
+    pub fn camera_up(&self) -> Vec3 {
+        let (_, u, v) = self.view_basis();
+        v * self.current_roll.cos() + u * self.current_roll.sin()
+    }
+
+    /// Roll of the camera in degrees, which is written to the scene next to the up vector that
+    /// is orbited around
+    pub fn roll_degrees(&self) -> f64 {
+        self.current_roll.to_degrees()
+    }
+
+    /// Right, view up and backwards unit vectors of the camera before it is rolled
+    fn view_basis(&self) -> (Vec3, Vec3, Vec3) {
+        let w = normalize(self.look_from() - self.look_at());
+        let u = camera_right(self.up, w, self.current_azimuth);
+        (w, u, cross(w, u))
+    }
+
+    /// Right, up and backwards unit vectors of the camera
+    fn camera_basis(&self) -> (Vec3, Vec3, Vec3) {
+        let (w, u, v) = self.view_basis();
+        let (sin, cos) = self.current_roll.sin_cos();
+        (u * cos - v * sin, v * cos + u * sin, w)
+    }
+
+    /// Direction of the ray through a point of the image, where 0, 0 is the top left and 1, 1
+    /// the bottom right corner
+    pub fn ray_direction(&self, x: f64, y: f64, aspect_ratio: f64) -> Vec3 {
+        let h = (self.vertical_fov_degrees.to_radians() / 2.).tan();
+        let (u, v, w) = self.camera_basis();
+        normalize(u * ((2. * x - 1.) * h * aspect_ratio) + v * ((1. - 2. * y) * h) - w)
+    }
+
+    /// Point of the image a position in the scene is seen at, where 0, 0 is the top left and
+    /// 1, 1 the bottom right corner. `None` for positions behind the camera
+    pub fn project(&self, point: Vec3, aspect_ratio: f64) -> Option<(f64, f64)> {
+        let h = (self.vertical_fov_degrees.to_radians() / 2.).tan();
+        let (u, v, w) = self.camera_basis();
+
+        let d = point - self.look_from();
+        let depth = -d.dot(w);
+        if depth <= 0. {
+            return None;
+        }
+        let x = d.dot(u) / (depth * h * aspect_ratio);
+        let y = d.dot(v) / (depth * h);
+        Some(((x + 1.) / 2., (1. - y) / 2.))
+    }
+
+    /// Moves look_at along the view direction, as the camera is focused at look_at
+    pub fn set_focus_distance(&mut self, distance: f64) {
+        let look_from = self.look_from();
+        let forward = normalize(self.look_at() - look_from);
+        self.set_view(look_from, look_from + forward * distance);
+    }
+
     pub fn orbit(&mut self, delta_azimuth: f64, delta_polar: f64) {
+        self.framing = false;
         self.target_azimuth += delta_azimuth;
-        self.target_polar = (self.target_polar + delta_polar).clamp(0.01, PI - 0.01);
+        self.target_polar = (self.target_polar + delta_polar).clamp(MIN_POLAR, PI - MIN_POLAR);
     }
 
-    pub fn zoom(&mut self, delta_distance: f64) {
-        self.target_distance = (self.target_distance + delta_distance).max(0.01);
+    /// Moves the camera closer or further away, by a fraction of the scene size
+    pub fn zoom(&mut self, delta: f64) {
+        self.framing = false;
+        self.target_distance =
+            (self.target_distance + delta * self.scene_size).max(self.scene_size * 0.001);
     }
 
-    pub fn pan(&mut self, delta_x: f64, delta_y: f64, up: Vec3) {
-        let look_from = self.look_from();
-        let look_at = self.look_at();
-        let forward = normalize(look_at - look_from);
-        let right = normalize(cross(forward, up));
-        let actual_up = normalize(cross(right, forward));
+    /// Moves the target along the right and up directions of the camera as seen on screen, by
+    /// fractions of the scene size
+    pub fn pan(&mut self, delta_x: f64, delta_y: f64) {
+        let (right, up, _) = self.camera_basis();
 
-        let pan_vector = right * -delta_x + actual_up * delta_y;
+        self.framing = false;
+        let pan_vector = (right * -delta_x + up * delta_y) * self.scene_size;
         self.target_target += pan_vector;
     }
+
+    pub fn roll(&mut self, delta_roll: f64) {
+        self.framing = false;
+        self.target_roll += delta_roll;
+    }
+
+    /// Camera position for the given orbit, in the frame of the up vector of the scene
+    fn position(&self, target: Vec3, distance: f64, azimuth: f64, polar: f64) -> Vec3 {
+        let (x, up, z) = basis(self.up);
+        target
+            + (x * (polar.sin() * azimuth.sin())
+                + up * polar.cos()
+                + z * (polar.sin() * azimuth.cos()))
+                * distance
+    }
 }
 
 impl From<&OrbitCamera> for CameraConfig {
@@ -127,11 +358,45 @@ impl From<&OrbitCamera> for CameraConfig {
             aperture_size: c.aperture_size,
             look_from: c.look_from(),
             look_at: c.look_at(),
-            up: c.up,
+            up: c.camera_up(),
         }
     }
 }
 
+/// Up vector of a camera tilted by the roll, where positive tilts the camera to the right
+pub fn rolled_up(up: Vec3, look_from: Vec3, look_at: Vec3, roll: f64) -> Vec3 {
+    let w = normalize(look_from - look_at);
+    let u = camera_right(up, w, 0.);
+    let v = cross(w, u);
+    v * roll.cos() + u * roll.sin()
+}
+
+/// Right unit vector of a camera with the backwards unit vector `w`, before it is rolled
+fn camera_right(up: Vec3, w: Vec3, azimuth: f64) -> Vec3 {
+    let u = cross(up, w);
+    if u.length_squared() < 1e-12 {
+        // Looking straight along the up vector, where the azimuth decides what is up
+        let (x, _, z) = basis(up);
+        return normalize(x * azimuth.cos() - z * azimuth.sin());
+    }
+    normalize(u)
+}
+
+/// Unit vectors the orbit is measured in, with the up vector of the scene in the middle. The
+/// azimuth is measured from the z axis towards the x axis, which for y up are the world axes.
+/// Otherwise the z axis is as close to the world z, or for z up to the negative world y, as it
+/// can be while perpendicular to up
+fn basis(up: Vec3) -> (Vec3, Vec3, Vec3) {
+    let up = normalize(up);
+    let reference = if up.z.abs() > 0.999 {
+        Vec3::new(0., -up.z.signum(), 0.)
+    } else {
+        Vec3::new(0., 0., 1.)
+    };
+    let z = normalize(reference - up * reference.dot(up));
+    (cross(up, z), up, z)
+}
+
 fn normalize(v: Vec3) -> Vec3 {
     let len = v.length();
     if len > 0. { v / len } else { v }
@@ -144,3 +409,164 @@ fn cross(a: Vec3, b: Vec3) -> Vec3 {
         a.x * b.y - a.y * b.x,
     )
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn camera() -> OrbitCamera {
+        camera_with_up(Vec3::new(0., 1., 0.), Vec3::new(0., 0., 10.))
+    }
+
+    fn camera_with_up(up: Vec3, look_from: Vec3) -> OrbitCamera {
+        let mut camera = OrbitCamera {
+            current_target: Vec3::new(0., 0., 0.),
+            current_distance: 0.,
+            current_azimuth: 0.,
+            current_polar: 0.,
+            current_roll: 0.,
+            target_target: Vec3::new(0., 0., 0.),
+            target_distance: 0.,
+            target_azimuth: 0.,
+            target_polar: 0.,
+            target_roll: 0.,
+            damping_factor: 1.,
+            vertical_fov_degrees: 60.,
+            aperture_size: 0.,
+            up,
+            scene_size: 1.,
+            framing: false,
+        };
+        camera.set_view(look_from, Vec3::new(0., 0., 0.));
+        camera
+    }
+
+    fn assert_near(expected: Vec3, actual: Vec3) {
+        assert!(
+            (expected - actual).length() < 1e-9,
+            "expected {expected:?} but got {actual:?}"
+        );
+    }
+
+    #[test]
+    fn frame() {
+        let mut camera = camera();
+
+        let bounds = Aabb::new_from_2_points(Vec3::new(1., 1., 1.), Vec3::new(3., 3., 3.));
+        camera.set_scene_bounds(&bounds);
+        assert!((camera.scene_size - 12f64.sqrt()).abs() < 1e-9);
+
+        camera.frame(&bounds, 2.);
+        assert_eq!(Vec3::new(2., 2., 2.), camera.target_target);
+        // The bounding sphere touches the top and bottom of the 60 degree field of view
+        assert!((camera.target_distance - 2. * 3f64.sqrt()).abs() < 1e-9);
+
+        // Framing is animated even without damping
+        assert!(camera.update());
+        assert!(camera.current_distance > camera.target_distance);
+        while camera.update() {}
+        assert!((camera.look_from() - Vec3::new(2., 2., 2. + 2. * 3f64.sqrt())).length() < 1e-9);
+
+        // Zooming and panning are relative to the framed box
+        camera.frame(
+            &Aabb::new_from_2_points(Vec3::new(0., 0., 0.), Vec3::new(0.1, 0.1, 0.1)),
+            2.,
+        );
+        assert!((camera.scene_size - 0.03f64.sqrt()).abs() < 1e-9);
+
+        // A huge ground sphere doesn't make the scene larger than the view
+        let ground = Aabb::new_from_2_points(
+            Vec3::new(-1000., -2000., -1000.),
+            Vec3::new(1000., 0., 1000.),
+        );
+        camera.set_scene_bounds(&ground);
+        assert!((camera.scene_size - 2. * camera.target_distance).abs() < 1e-9);
+    }
+
+    #[test]
+    fn snap_to() {
+        let mut camera = camera();
+        camera.orbit(2. * PI + 0.5, 0.);
+
+        camera.snap_to(View::Left);
+        assert!((camera.target_azimuth - (2. * PI - PI / 2.)).abs() < 1e-9);
+        camera.snap_to(View::Top);
+        assert_eq!(MIN_POLAR, camera.target_polar);
+        while camera.update() {}
+        let look_from = camera.look_from();
+        assert!(look_from.x.abs() < 1e-9 && look_from.y > 9.99 && look_from.z > 0.09);
+
+        camera.snap_to(View::Right);
+        while camera.update() {}
+        assert!((camera.look_from() - Vec3::new(10., 0., 0.)).length() < 1e-9);
+    }
+
+    #[test]
+    fn z_up() {
+        let mut camera = camera_with_up(Vec3::new(0., 0., 1.), Vec3::new(0., -10., 0.));
+        assert_near(Vec3::new(0., -10., 0.), camera.look_from());
+        assert!((camera.current_polar - PI / 2.).abs() < 1e-9);
+
+        // Orbiting upwards moves towards the z axis
+        camera.orbit(0., -PI / 4.);
+        camera.update();
+        let h = 10. / 2f64.sqrt();
+        assert_near(Vec3::new(0., -h, h), camera.look_from());
+
+        // Orbiting sideways keeps the height
+        camera.orbit(PI / 2., 0.);
+        camera.update();
+        assert_near(Vec3::new(h, 0., h), camera.look_from());
+
+        camera.snap_to(View::Front);
+        camera.update();
+        assert_near(Vec3::new(0., -10., 0.), camera.look_from());
+        assert_near(Vec3::new(0., 0., 1.), camera.camera_up());
+
+        // Panning up moves the target along z
+        camera.pan(0., 1.);
+        camera.update();
+        assert_near(Vec3::new(0., 0., 1.), camera.look_at());
+    }
+
+    #[test]
+    fn roll() {
+        let mut camera = camera();
+        camera.roll(PI / 2.);
+        camera.update();
+        // Tilted to the right, the world x axis points up on screen
+        assert_near(Vec3::new(1., 0., 0.), camera.camera_up());
+
+        let (x, y) = camera.project(Vec3::new(1., 0., 0.), 1.).unwrap();
+        assert!((x - 0.5).abs() < 1e-9 && y < 0.5);
+        let direction = camera.ray_direction(x, y, 1.);
+        assert_near(normalize(Vec3::new(1., 0., -10.)), direction);
+
+        camera.pan(1., 0.);
+        camera.update();
+        assert_near(Vec3::new(0., 1., 0.), camera.look_at());
+    }
+
+    #[test]
+    fn rolled_up_matches_camera() {
+        let mut camera = camera_with_up(Vec3::new(0., 0., 1.), Vec3::new(3., -10., 2.));
+        camera.set_roll(0.3);
+        // The scene camera is rolled the same way, so the roll survives writing the view to the
+        // scene and loading it again
+        assert_near(
+            camera.camera_up(),
+            rolled_up(camera.up, camera.look_from(), camera.look_at(), 0.3),
+        );
+        assert_eq!(Vec3::new(0., 0., 1.), camera.up);
+
+        // Looking straight down the up vector
+        let up = rolled_up(
+            Vec3::new(0., 1., 0.),
+            Vec3::new(0., 10., 0.),
+            Vec3::new(0., 0., 0.),
+            PI / 2.,
+        );
+        assert!(!up.x.is_nan() && (up.length() - 1.).abs() < 1e-9, "{up:?}");
+        assert!(up.y.abs() < 1e-9);
+    }
+}
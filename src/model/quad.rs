@@ -2,12 +2,16 @@ use std::collections::HashMap;
 use std::error::Error;
 
 use serde::{Deserialize, Serialize};
+use solstrale::geo::Aabb;
+use solstrale::geo::transformation::Transformer;
+use solstrale::geo::vec3::Vec3;
 use solstrale::hittable::Hittables;
 
 use crate::model::FieldType::{Normal, Optional, OptionalList};
 use crate::model::material::Material;
 use crate::model::pos::Pos;
-use crate::model::transformation::{Transformation, create_transformation};
+use crate::model::ray_cast::{BoundingBox, RayCast, aabb_from_points, ray_cast_quad};
+use crate::model::transformation::{Transformation, create_transformation, transformer};
 use crate::model::{Creator, CreatorContext, DocumentationStructure, FieldInfo, HelpDocumentation};
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
@@ -38,6 +42,29 @@ impl Creator<Hittables> for Quad {
     }
 }
 
+impl RayCast for Quad {
+    fn ray_cast(&self, origin: Vec3, direction: Vec3) -> Result<Option<f64>, Box<dyn Error>> {
+        Ok(ray_cast_quad(
+            origin,
+            direction,
+            (&self.q).into(),
+            (&self.u).into(),
+            (&self.v).into(),
+            &transformer(&self.transformations)?,
+        ))
+    }
+}
+
+impl BoundingBox for Quad {
+    fn bounding_box(&self) -> Result<Aabb, Box<dyn Error>> {
+        let transformer = transformer(&self.transformations)?;
+        let q = transformer.transform((&self.q).into(), false);
+        let u = transformer.transform((&self.u).into(), true);
+        let v = transformer.transform((&self.v).into(), true);
+        Ok(aabb_from_points(&[q, q + u, q + v, q + u + v]))
+    }
+}
+
 impl HelpDocumentation for Quad {
     fn get_documentation_structure(depth: u8) -> DocumentationStructure {
         DocumentationStructure {
@@ -10,7 +10,13 @@ pub struct QuarterScreenWidthHeight {}
 
 impl Creator<(usize, usize)> for QuarterScreenWidthHeight {
     fn create(&self, ctx: &CreatorContext) -> Result<(usize, usize), Box<dyn Error>> {
-        Ok((ctx.screen_width / 4, ctx.screen_height / 4))
+        Ok(self.size(ctx.screen_width, ctx.screen_height))
+    }
+}
+
+impl QuarterScreenWidthHeight {
+    pub fn size(&self, screen_width: usize, screen_height: usize) -> (usize, usize) {
+        (screen_width / 4, screen_height / 4)
     }
 }
 
@@ -0,0 +1,273 @@
+use std::error::Error;
+
+use solstrale::geo::Aabb;
+use solstrale::geo::transformation::Transformer;
+use solstrale::geo::vec3::Vec3;
+
+use crate::model::scene::Scene;
+
+/// Rays closer than this are ignored, so that a ray starting on a surface does not hit it
+const MIN_DISTANCE: f64 = 0.001;
+
+/// Intersection with rays, computed on the CPU from the scene model. Used for interactions in
+/// the viewport, like clicking on an object
+pub trait RayCast {
+    /// Distance along the ray to the closest hit. The direction must be a unit vector
+    fn ray_cast(&self, origin: Vec3, direction: Vec3) -> Result<Option<f64>, Box<dyn Error>>;
+}
+
+/// Axis aligned box containing an object of the scene model, for highlighting and framing
+/// objects in the viewport
+pub trait BoundingBox {
+    fn bounding_box(&self) -> Result<Aabb, Box<dyn Error>>;
+}
+
+/// The closest object hit by a ray
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+    /// Index of the hit object in the `world` of the scene
+    pub index: usize,
+    pub distance: f64,
+}
+
+impl Scene {
+    pub fn ray_cast(
+        &self,
+        origin: Vec3,
+        direction: Vec3,
+    ) -> Result<Option<RayHit>, Box<dyn Error>> {
+        let mut closest: Option<RayHit> = None;
+        for (index, hittable) in self.world.iter().enumerate() {
+            if let Some(distance) = hittable.ray_cast(origin, direction)?
+                && closest.is_none_or(|c| distance < c.distance)
+            {
+                closest = Some(RayHit { index, distance });
+            }
+        }
+        Ok(closest)
+    }
+}
+
+impl BoundingBox for Scene {
+    fn bounding_box(&self) -> Result<Aabb, Box<dyn Error>> {
+        let mut aabb: Option<Aabb> = None;
+        for hittable in &self.world {
+            let b = hittable.bounding_box()?;
+            aabb = Some(match aabb {
+                Some(a) => a.combine(&b),
+                None => b,
+            });
+        }
+        Ok(aabb.unwrap_or_else(|| aabb_from_points(&[])))
+    }
+}
+
+/// Smallest box containing all of the points
+pub fn aabb_from_points(points: &[Vec3]) -> Aabb {
+    points
+        .iter()
+        .map(|p| Aabb::new_from_2_points(*p, *p))
+        .reduce(|a, b| a.combine(&b))
+        .unwrap_or_else(|| Aabb::new_from_2_points(Vec3::new(0., 0., 0.), Vec3::new(0., 0., 0.)))
+}
+
+/// Corners of a box after transformation
+pub fn box_corners(a: Vec3, b: Vec3, transformer: &dyn Transformer) -> [Vec3; 8] {
+    [0, 1, 2, 3, 4, 5, 6, 7].map(|i| {
+        transformer.transform(
+            Vec3::new(
+                if i & 1 == 0 { a.x } else { b.x },
+                if i & 2 == 0 { a.y } else { b.y },
+                if i & 4 == 0 { a.z } else { b.z },
+            ),
+            false,
+        )
+    })
+}
+
+pub fn ray_cast_sphere(origin: Vec3, direction: Vec3, center: Vec3, radius: f64) -> Option<f64> {
+    let oc = origin - center;
+    let half_b = oc.dot(direction);
+    let c = oc.length_squared() - radius * radius;
+    let discriminant = half_b * half_b - c;
+    if discriminant < 0. {
+        return None;
+    }
+
+    let sqrt_d = discriminant.sqrt();
+    [-half_b - sqrt_d, -half_b + sqrt_d]
+        .into_iter()
+        .find(|t| *t > MIN_DISTANCE)
+}
+
+/// Hit with the parallelogram spanned by `u` and `v` from the corner `q`, after transformation
+pub fn ray_cast_quad(
+    origin: Vec3,
+    direction: Vec3,
+    q: Vec3,
+    u: Vec3,
+    v: Vec3,
+    transformer: &dyn Transformer,
+) -> Option<f64> {
+    let q = transformer.transform(q, false);
+    let u = transformer.transform(u, true);
+    let v = transformer.transform(v, true);
+
+    let n = u.cross(v);
+    let denominator = n.dot(direction);
+    if denominator.abs() < 1e-12 {
+        return None;
+    }
+    let t = n.dot(q - origin) / denominator;
+    if t <= MIN_DISTANCE {
+        return None;
+    }
+
+    let w = n / n.dot(n);
+    let planar_hit = origin + direction * t - q;
+    let alpha = w.dot(planar_hit.cross(v));
+    let beta = w.dot(u.cross(planar_hit));
+    ((0. ..=1.).contains(&alpha) && (0. ..=1.).contains(&beta)).then_some(t)
+}
+
+/// Hit with the box between the corners `a` and `b`, after transformation. Since the
+/// transformations are affine, the ray is instead moved into the space of the box
+pub fn ray_cast_box(
+    origin: Vec3,
+    direction: Vec3,
+    a: Vec3,
+    b: Vec3,
+    transformer: &dyn Transformer,
+) -> Option<f64> {
+    let min = Vec3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z));
+    let max = Vec3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z));
+
+    let corner = transformer.transform(min, false);
+    let ex = transformer.transform(Vec3::new(max.x - min.x, 0., 0.), true);
+    let ey = transformer.transform(Vec3::new(0., max.y - min.y, 0.), true);
+    let ez = transformer.transform(Vec3::new(0., 0., max.z - min.z), true);
+
+    let det = ex.dot(ey.cross(ez));
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let rows = [ey.cross(ez) / det, ez.cross(ex) / det, ex.cross(ey) / det];
+    let local = |v: Vec3| Vec3::new(rows[0].dot(v), rows[1].dot(v), rows[2].dot(v));
+
+    ray_cast_unit_cube(local(origin - corner), local(direction))
+}
+
+/// Hit with a bounding box, used for objects where the exact shape is not known
+pub fn ray_cast_aabb(origin: Vec3, direction: Vec3, aabb: &Aabb) -> Option<f64> {
+    let size = Vec3::new(aabb.x.size(), aabb.y.size(), aabb.z.size());
+    let min = Vec3::new(aabb.x.min, aabb.y.min, aabb.z.min);
+    let scale = |v: Vec3| {
+        Vec3::new(
+            v.x / size.x.max(f64::EPSILON),
+            v.y / size.y.max(f64::EPSILON),
+            v.z / size.z.max(f64::EPSILON),
+        )
+    };
+    ray_cast_unit_cube(scale(origin - min), scale(direction))
+}
+
+/// Slab test against the cube from 0, 0, 0 to 1, 1, 1. The distance is in units of the
+/// direction, which is the same as the distance of the untransformed ray
+fn ray_cast_unit_cube(origin: Vec3, direction: Vec3) -> Option<f64> {
+    let mut t_min = f64::NEG_INFINITY;
+    let mut t_max = f64::INFINITY;
+
+    for (o, d) in [
+        (origin.x, direction.x),
+        (origin.y, direction.y),
+        (origin.z, direction.z),
+    ] {
+        if d.abs() < 1e-12 {
+            if !(0. ..=1.).contains(&o) {
+                return None;
+            }
+            continue;
+        }
+        let t0 = -o / d;
+        let t1 = (1. - o) / d;
+        t_min = t_min.max(t0.min(t1));
+        t_max = t_max.min(t0.max(t1));
+    }
+
+    if t_min > t_max {
+        return None;
+    }
+    [t_min, t_max].into_iter().find(|t| *t > MIN_DISTANCE)
+}
+
+#[cfg(test)]
+mod test {
+    use solstrale::geo::transformation::{NopTransformer, RotationY, Transformations, Translation};
+
+    use super::*;
+
+    fn assert_near(expected: f64, actual: Option<f64>) {
+        let actual = actual.expect("expected a hit");
+        assert!(
+            (expected - actual).abs() < 1e-6,
+            "expected {expected} but got {actual}"
+        );
+    }
+
+    #[test]
+    fn shapes() {
+        let origin = Vec3::new(0., 0., -10.);
+        let forward = Vec3::new(0., 0., 1.);
+
+        assert_near(
+            8.,
+            ray_cast_sphere(origin, forward, Vec3::new(0., 0., 0.), 2.),
+        );
+        assert_eq!(
+            None,
+            ray_cast_sphere(origin, forward, Vec3::new(3., 0., 0.), 2.)
+        );
+
+        assert_near(
+            10.,
+            ray_cast_quad(
+                origin,
+                forward,
+                Vec3::new(-1., -1., 0.),
+                Vec3::new(2., 0., 0.),
+                Vec3::new(0., 2., 0.),
+                &NopTransformer(),
+            ),
+        );
+
+        assert_near(
+            9.,
+            ray_cast_box(
+                origin,
+                forward,
+                Vec3::new(-1., -1., -1.),
+                Vec3::new(1., 1., 1.),
+                &NopTransformer(),
+            ),
+        );
+        // A box rotated by 45 degrees has its edge closer to the ray origin
+        let rotated = Transformations::new(vec![
+            Box::new(RotationY::new(45.)),
+            Box::new(Translation::new(Vec3::new(0., 0., 1.))),
+        ]);
+        assert_near(
+            11. - 2_f64.sqrt(),
+            ray_cast_box(
+                origin,
+                forward,
+                Vec3::new(-1., -1., -1.),
+                Vec3::new(1., 1., 1.),
+                &rotated,
+            ),
+        );
+
+        let aabb = Aabb::new_from_2_points(Vec3::new(-1., -1., 2.), Vec3::new(1., 1., 4.));
+        assert_near(12., ray_cast_aabb(origin, forward, &aabb));
+        assert_eq!(None, ray_cast_aabb(origin, Vec3::new(0., 0., -1.), &aabb));
+    }
+}
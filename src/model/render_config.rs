@@ -9,18 +9,29 @@ use solstrale::renderer::RenderImageStrategy;
 use crate::model::FieldType::{Optional, OptionalList};
 use crate::model::post_processor::PostProcessor;
 use crate::model::width_height::WidthHeight;
-use crate::model::{Creator, CreatorContext, DocumentationStructure, FieldInfo, HelpDocumentation};
+use crate::model::{
+    Creator, CreatorContext, DocumentationStructure, FieldInfo, HelpDocumentation,
+    deserialize_rounded_option,
+};
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Default, Clone)]
 #[serde(deny_unknown_fields)]
 pub struct RenderConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub width_height: Option<WidthHeight>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        deserialize_with = "deserialize_rounded_option"
+    )]
     pub samples_per_pixel: Option<u32>,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub post_processors: Vec<PostProcessor>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        deserialize_with = "deserialize_rounded_option"
+    )]
     pub preview_interval_ms: Option<i64>,
 }
 
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::error::Error;
 
 use serde::{Deserialize, Serialize};
@@ -9,7 +9,9 @@ use crate::model::camera_config::CameraConfig;
 use crate::model::hittable::Hittable;
 use crate::model::render_config::RenderConfig;
 use crate::model::rgb::Rgb;
-use crate::model::{Creator, CreatorContext, DocumentationStructure, FieldInfo, HelpDocumentation};
+use crate::model::{
+    Creator, CreatorContext, DocumentationStructure, FieldInfo, HelpDocumentation, ModelError,
+};
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 #[serde(deny_unknown_fields)]
@@ -20,6 +22,32 @@ pub struct Scene {
     pub background_color: Option<Rgb>,
     pub camera: CameraConfig,
     pub world: Vec<Hittable>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    pub variants: BTreeMap<String, serde_yaml::Value>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    pub cameras: BTreeMap<String, CameraConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_camera: Option<String>,
+}
+
+impl Scene {
+    /// The camera to render with, which is the bookmarked camera named by `active_camera` if set
+    pub fn active_camera(&self) -> Result<&CameraConfig, Box<dyn Error>> {
+        match &self.active_camera {
+            None => Ok(&self.camera),
+            Some(name) => self.cameras.get(name).ok_or_else(|| {
+                let message = if self.cameras.is_empty() {
+                    format!("Unknown camera '{name}', the scene has no cameras")
+                } else {
+                    format!(
+                        "Unknown camera '{name}', available cameras are: {}",
+                        self.cameras.keys().cloned().collect::<Vec<_>>().join(", ")
+                    )
+                };
+                Box::new(ModelError::new(&message)).into()
+            }),
+        }
+    }
 }
 
 impl Creator<solstrale::renderer::Scene> for Scene {
@@ -62,9 +90,42 @@ impl HelpDocumentation for Scene {
             \x20\x20- sqrt(v)\n\
             \x20\x20- abs(v)\n\
             \x20\x20- len(x, y, z)\n\
-            \x20\x20- range(start, end, step_by)\n\n\
+            \x20\x20- range(start, end, step_by)\n\
+            \x20\x20- tan(v)\n\
+            \x20\x20- atan2(y, x)\n\
+            \x20\x20- pow(v, exp)\n\
+            \x20\x20- exp(v)\n\
+            \x20\x20- ln(v)\n\
+            \x20\x20- floor(v), ceil(v), round(v)\n\
+            \x20\x20- min(a, b), max(a, b)\n\
+            \x20\x20- clamp(v, min, max)\n\
+            \x20\x20- lerp(a, b, t)\n\
+            \x20\x20- smoothstep(edge0, edge1, v)\n\
+            \x20\x20- pi()\n\
+            \x20\x20- normalize(v), cross(a, b), dot(a, b) where vectors are given as \"x, y, z\"\n\
+            \x20\x20- hsv_to_rgb(h, s, v) with hue in degrees and saturation and value from 0 to 1\n\
+            \x20\x20- mix(a, b, t) for blending two \"r, g, b\" colors\n\
+            \x20\x20- random(seed, min, max) and random_int(seed, min, max)\n\
+            \x20\x20- random_in_sphere(seed, radius) and random_on_hemisphere(seed, normal)\n\
+            \x20\x20- noise(x, y, z, seed) for smooth Perlin noise between -1 and 1\n\n\
+            The random functions always give the same result for the same arguments, so vary the seed with for example a loop variable or frameIndex\n\n\
             The following variables are also available:\n\
-            \x20\x20- frameIndex (Useful for batch rendering)\n\n\
+            \x20\x20- frameIndex (Useful for batch rendering)\n\
+            \x20\x20- frameCount (Number of frames in the batch rendering)\n\
+            \x20\x20- time (Seconds since the first frame, given the frame rate of the batch rendering)\n\
+            \x20\x20- normalizedTime (Goes from 0 on the first frame to 1 on the last frame)\n\
+            \x20\x20- width and height (Size of the rendered image in pixels, as given by width_height)\n\
+            \x20\x20- mode (Either \"interactive\" when rendering in this app or \"batch\" in the batch renderer)\n\
+            \x20\x20- any variables passed with --var to the batch renderer\n\n\
+            Any number, position or color in the scene can also be animated with keyframes, which are interpolated by frameIndex. For example:\n\n\
+            radius:\n\
+            \x20\x20keyframes:\n\
+            \x20\x20\x20\x20- frame: 0\n\
+            \x20\x20\x20\x20\x20\x20value: 1\n\
+            \x20\x20\x20\x20- frame: 50\n\
+            \x20\x20\x20\x20\x20\x20value: 3\n\
+            \x20\x20\x20\x20\x20\x20ease: in_out\n\n\
+            The ease of a keyframe is used from the previous keyframe, and can be linear (default), step, in, out, in_out or bezier: [x1, y1, x2, y2]\n\n\
             Use ctrl+space to autocomplete configuration keys and ctrl+r to restart the rendering\n\n\
             Progress bar shows percentage completed, remaining time, FPS (frames rendered per second) and MPPS (Million pixel samples rendered per second)"
                     .to_string(),
@@ -101,6 +162,33 @@ impl HelpDocumentation for Scene {
                         Hittable::get_documentation_structure(depth + 1),
                     ),
                 ),
+                (
+                    "cameras".to_string(),
+                    FieldInfo::new(
+                        "Named camera bookmarks, for rendering the scene from several viewpoints. Each name maps to a camera configuration",
+                        Optional,
+                        CameraConfig::get_documentation_structure(depth + 1),
+                    ),
+                ),
+                (
+                    "active_camera".to_string(),
+                    FieldInfo::new_simple(
+                        "Name of the camera bookmark to render with. Defaults to the 'camera' of the scene",
+                        Optional,
+                        "Name of a camera in 'cameras'",
+                    ),
+                ),
+                (
+                    "variants".to_string(),
+                    FieldInfo::new_simple(
+                        "Named variants of the scene, for example draft and final quality. Each variant is a partial scene that is merged over the rest of the scene when selected",
+                        Optional,
+                        "Map from variant name to the scene values it overrides. For example:\n\n\
+                        draft:\n\
+                        \x20\x20render_configuration:\n\
+                        \x20\x20\x20\x20samples_per_pixel: 10",
+                    ),
+                ),
             ]),
         }
     }
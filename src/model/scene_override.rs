@@ -0,0 +1,203 @@
+use std::error::Error;
+use std::str::FromStr;
+
+use serde_yaml::{Mapping, Value};
+
+use crate::model::ModelError;
+
+/// A single `path=value` override of a value in the scene document, for example
+/// `camera.vertical_fov_degrees=40` or `world[2].sphere.radius=3`
+#[derive(Clone, Debug, PartialEq)]
+pub struct SceneOverride {
+    pub path: Vec<PathSegment>,
+    pub value: Value,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// A `name=value` variable made available to the Tera templates of the scene
+#[derive(Clone, Debug, PartialEq)]
+pub struct TemplateVariable {
+    pub name: String,
+    pub value: Value,
+}
+
+impl FromStr for SceneOverride {
+    type Err = ModelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (path, value) = split_assignment(s)?;
+        Ok(SceneOverride {
+            path: parse_path(path)?,
+            value: parse_value(value)?,
+        })
+    }
+}
+
+impl FromStr for TemplateVariable {
+    type Err = ModelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, value) = split_assignment(s)?;
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return Err(ModelError::new(&format!(
+                "Invalid variable name '{name}', only letters, digits and '_' are allowed"
+            )));
+        }
+        Ok(TemplateVariable {
+            name: name.to_string(),
+            value: parse_value(value)?,
+        })
+    }
+}
+
+fn split_assignment(s: &str) -> Result<(&str, &str), ModelError> {
+    s.split_once('=')
+        .map(|(k, v)| (k.trim(), v.trim()))
+        .ok_or_else(|| ModelError::new(&format!("Expected format 'name=value' but got '{s}'")))
+}
+
+fn parse_value(value: &str) -> Result<Value, ModelError> {
+    serde_yaml::from_str(value).map_err(|err| ModelError::new_from_err(Box::new(err)))
+}
+
+fn parse_path(path: &str) -> Result<Vec<PathSegment>, ModelError> {
+    let invalid_path = || ModelError::new(&format!("Invalid scene path '{path}'"));
+    let mut segments = Vec::new();
+
+    for part in path.split('.') {
+        let (key, mut indices) = match part.find('[') {
+            Some(i) => (&part[..i], &part[i..]),
+            None => (part, ""),
+        };
+        if key.is_empty() {
+            return Err(invalid_path());
+        }
+        segments.push(PathSegment::Key(key.to_string()));
+
+        while !indices.is_empty() {
+            let end = indices.find(']').ok_or_else(invalid_path)?;
+            if !indices.starts_with('[') {
+                return Err(invalid_path());
+            }
+            let index = indices[1..end]
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| invalid_path())?;
+            segments.push(PathSegment::Index(index));
+            indices = &indices[end + 1..];
+        }
+    }
+
+    Ok(segments)
+}
+
+/// Applies the overrides in order to a parsed scene document. Missing keys are created,
+/// but list indices must refer to existing elements
+pub fn apply_overrides(
+    document: &mut Value,
+    overrides: &[SceneOverride],
+) -> Result<(), Box<dyn Error>> {
+    for o in overrides {
+        let mut current = &mut *document;
+        for segment in &o.path {
+            current = match segment {
+                PathSegment::Key(key) => {
+                    if current.is_null() {
+                        *current = Value::Mapping(Mapping::new());
+                    }
+                    current
+                        .as_mapping_mut()
+                        .ok_or_else(|| override_error(o, &format!("'{key}' is not in a map")))?
+                        .entry(Value::String(key.clone()))
+                        .or_insert(Value::Null)
+                }
+                PathSegment::Index(index) => current
+                    .as_sequence_mut()
+                    .ok_or_else(|| override_error(o, &format!("[{index}] is not in a list")))?
+                    .get_mut(*index)
+                    .ok_or_else(|| override_error(o, &format!("[{index}] is out of bounds")))?,
+            };
+        }
+        *current = o.value.clone();
+    }
+    Ok(())
+}
+
+fn override_error(o: &SceneOverride, message: &str) -> ModelError {
+    let path: String = o
+        .path
+        .iter()
+        .enumerate()
+        .map(|(i, s)| match s {
+            PathSegment::Key(k) if i == 0 => k.clone(),
+            PathSegment::Key(k) => format!(".{k}"),
+            PathSegment::Index(i) => format!("[{i}]"),
+        })
+        .collect();
+    ModelError::new(&format!("Failed to override '{path}': {message}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_override() {
+        let o: SceneOverride = "world[2].sphere.radius=3".parse().unwrap();
+        assert_eq!(
+            vec![
+                PathSegment::Key("world".to_string()),
+                PathSegment::Index(2),
+                PathSegment::Key("sphere".to_string()),
+                PathSegment::Key("radius".to_string()),
+            ],
+            o.path
+        );
+        assert_eq!(Value::from(3), o.value);
+
+        assert!("world[x].sphere=1".parse::<SceneOverride>().is_err());
+        assert!("camera..up=1".parse::<SceneOverride>().is_err());
+        assert!("camera".parse::<SceneOverride>().is_err());
+    }
+
+    #[test]
+    fn apply() {
+        let mut document: Value = serde_yaml::from_str(
+            "camera:
+  look_from: 0, 0, 0
+world:
+- sphere:
+    radius: 1
+",
+        )
+        .unwrap();
+
+        let overrides: Vec<SceneOverride> = vec![
+            "camera.vertical_fov_degrees=40".parse().unwrap(),
+            "world[0].sphere.radius=3".parse().unwrap(),
+            "render_configuration.samples_per_pixel=16".parse().unwrap(),
+        ];
+        apply_overrides(&mut document, &overrides).unwrap();
+
+        assert_eq!(
+            "camera:
+  look_from: 0, 0, 0
+  vertical_fov_degrees: 40
+world:
+- sphere:
+    radius: 3
+render_configuration:
+  samples_per_pixel: 16
+",
+            serde_yaml::to_string(&document).unwrap()
+        );
+
+        let out_of_bounds: SceneOverride = "world[1].sphere.radius=3".parse().unwrap();
+        assert!(apply_overrides(&mut document, &[out_of_bounds]).is_err());
+    }
+}
@@ -10,7 +10,13 @@ pub struct ScreenWidthHeight {}
 
 impl Creator<(usize, usize)> for ScreenWidthHeight {
     fn create(&self, ctx: &CreatorContext) -> Result<(usize, usize), Box<dyn Error>> {
-        Ok((ctx.screen_width, ctx.screen_height))
+        Ok(self.size(ctx.screen_width, ctx.screen_height))
+    }
+}
+
+impl ScreenWidthHeight {
+    pub fn size(&self, screen_width: usize, screen_height: usize) -> (usize, usize) {
+        (screen_width, screen_height)
     }
 }
 
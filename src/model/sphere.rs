@@ -1,8 +1,11 @@
 use crate::model::FieldType::{Normal, Optional};
 use crate::model::material::Material;
 use crate::model::pos::Pos;
+use crate::model::ray_cast::{BoundingBox, RayCast, ray_cast_sphere};
 use crate::model::{Creator, CreatorContext, DocumentationStructure, FieldInfo, HelpDocumentation};
 use serde::{Deserialize, Serialize};
+use solstrale::geo::Aabb;
+use solstrale::geo::vec3::Vec3;
 use solstrale::hittable::Hittables;
 use std::collections::HashMap;
 use std::error::Error;
@@ -30,6 +33,25 @@ impl Creator<Hittables> for Sphere {
     }
 }
 
+impl RayCast for Sphere {
+    fn ray_cast(&self, origin: Vec3, direction: Vec3) -> Result<Option<f64>, Box<dyn Error>> {
+        Ok(ray_cast_sphere(
+            origin,
+            direction,
+            (&self.center).into(),
+            self.radius,
+        ))
+    }
+}
+
+impl BoundingBox for Sphere {
+    fn bounding_box(&self) -> Result<Aabb, Box<dyn Error>> {
+        let center: Vec3 = (&self.center).into();
+        let r = Vec3::new(self.radius, self.radius, self.radius);
+        Ok(Aabb::new_from_2_points(center - r, center + r))
+    }
+}
+
 impl HelpDocumentation for Sphere {
     fn get_documentation_structure(depth: u8) -> DocumentationStructure {
         DocumentationStructure {
@@ -1,11 +1,17 @@
 use std::collections::HashMap;
 use std::error::Error;
+use std::f64::consts::PI;
 
 use serde_json::{Value, from_value, to_value};
 use solstrale::geo::vec3::Vec3;
 use tera::Tera;
 
-pub fn apply_template(templated_yaml: &str, frame_index: usize) -> Result<String, Box<dyn Error>> {
+use crate::model::TemplateContext;
+
+pub fn apply_template(
+    templated_yaml: &str,
+    template_ctx: &TemplateContext,
+) -> Result<String, Box<dyn Error>> {
     let mut tera = Tera::default();
 
     tera.register_function("sin", sin);
@@ -14,11 +20,54 @@ pub fn apply_template(templated_yaml: &str, frame_index: usize) -> Result<String
     tera.register_function("abs", abs);
     tera.register_function("len", len);
     tera.register_function("range", range);
+    tera.register_function("tan", tan);
+    tera.register_function("atan2", atan2);
+    tera.register_function("pow", pow);
+    tera.register_function("exp", exp);
+    tera.register_function("ln", ln);
+    tera.register_function("floor", floor);
+    tera.register_function("ceil", ceil);
+    tera.register_function("round", round);
+    tera.register_function("min", min);
+    tera.register_function("max", max);
+    tera.register_function("clamp", clamp);
+    tera.register_function("lerp", lerp);
+    tera.register_function("smoothstep", smoothstep);
+    tera.register_function("pi", pi);
+    tera.register_function("normalize", normalize);
+    tera.register_function("cross", cross);
+    tera.register_function("dot", dot);
+    tera.register_function("hsv_to_rgb", hsv_to_rgb);
+    tera.register_function("mix", mix);
+    tera.register_function("random", random);
+    tera.register_function("random_int", random_int);
+    tera.register_function("random_in_sphere", random_in_sphere);
+    tera.register_function("random_on_hemisphere", random_on_hemisphere);
+    tera.register_function("noise", noise);
 
     tera.add_raw_template("template", templated_yaml)?;
 
     let mut context = tera::Context::new();
-    context.try_insert("frameIndex", &frame_index)?;
+    for variable in &template_ctx.variables {
+        context.try_insert(&variable.name, &variable.value)?;
+    }
+    context.try_insert("frameIndex", &template_ctx.frame_index)?;
+    context.try_insert("frameCount", &template_ctx.frame_count)?;
+    context.try_insert(
+        "time",
+        &(template_ctx.frame_index as f64 / template_ctx.fps),
+    )?;
+    context.try_insert(
+        "normalizedTime",
+        &if template_ctx.frame_count > 1 {
+            template_ctx.frame_index as f64 / (template_ctx.frame_count - 1) as f64
+        } else {
+            0.
+        },
+    )?;
+    context.try_insert("width", &template_ctx.width)?;
+    context.try_insert("height", &template_ctx.height)?;
+    context.try_insert("mode", &template_ctx.mode.to_string())?;
 
     Ok(tera.render("template", &context)?)
 }
@@ -78,6 +127,347 @@ pub fn range(args: &HashMap<String, Value>) -> tera::Result<Value> {
     Ok(to_value(res)?)
 }
 
+pub fn tan(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let v = get_required_numeric_arg(args, "tan", "v")?;
+    Ok(to_value(v.tan())?)
+}
+
+pub fn atan2(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let y = get_required_numeric_arg(args, "atan2", "y")?;
+    let x = get_required_numeric_arg(args, "atan2", "x")?;
+    Ok(to_value(y.atan2(x))?)
+}
+
+pub fn pow(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let v = get_required_numeric_arg(args, "pow", "v")?;
+    let exp = get_required_numeric_arg(args, "pow", "exp")?;
+    let result = v.powf(exp);
+
+    if !result.is_finite() {
+        return Err(tera::Error::msg(format!(
+            "Function `pow` was called with `v` argument {v} and `exp` argument {exp}, which has no finite result"
+        )));
+    }
+
+    Ok(to_value(result)?)
+}
+
+pub fn exp(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let v = get_required_numeric_arg(args, "exp", "v")?;
+    Ok(to_value(v.exp())?)
+}
+
+pub fn ln(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let v = get_required_numeric_arg(args, "ln", "v")?;
+
+    if v <= 0. {
+        return Err(tera::Error::msg(format!(
+            "Function `ln` was called with non-positive `v` argument: {v}"
+        )));
+    }
+
+    Ok(to_value(v.ln())?)
+}
+
+pub fn floor(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let v = get_required_numeric_arg(args, "floor", "v")?;
+    Ok(to_value(v.floor())?)
+}
+
+pub fn ceil(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let v = get_required_numeric_arg(args, "ceil", "v")?;
+    Ok(to_value(v.ceil())?)
+}
+
+pub fn round(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let v = get_required_numeric_arg(args, "round", "v")?;
+    Ok(to_value(v.round())?)
+}
+
+pub fn min(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let a = get_required_numeric_arg(args, "min", "a")?;
+    let b = get_required_numeric_arg(args, "min", "b")?;
+    Ok(to_value(a.min(b))?)
+}
+
+pub fn max(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let a = get_required_numeric_arg(args, "max", "a")?;
+    let b = get_required_numeric_arg(args, "max", "b")?;
+    Ok(to_value(a.max(b))?)
+}
+
+pub fn clamp(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let v = get_required_numeric_arg(args, "clamp", "v")?;
+    let min = get_required_numeric_arg(args, "clamp", "min")?;
+    let max = get_required_numeric_arg(args, "clamp", "max")?;
+
+    if min > max {
+        return Err(tera::Error::msg(
+            "Function `clamp` was called with a `min` argument greater than the `max` one",
+        ));
+    }
+
+    Ok(to_value(v.clamp(min, max))?)
+}
+
+pub fn lerp(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let a = get_required_numeric_arg(args, "lerp", "a")?;
+    let b = get_required_numeric_arg(args, "lerp", "b")?;
+    let t = get_required_numeric_arg(args, "lerp", "t")?;
+    Ok(to_value(a + (b - a) * t)?)
+}
+
+pub fn smoothstep(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let edge0 = get_required_numeric_arg(args, "smoothstep", "edge0")?;
+    let edge1 = get_required_numeric_arg(args, "smoothstep", "edge1")?;
+    let v = get_required_numeric_arg(args, "smoothstep", "v")?;
+
+    if edge0 == edge1 {
+        return Err(tera::Error::msg(
+            "Function `smoothstep` was called with equal `edge0` and `edge1` arguments",
+        ));
+    }
+
+    let t = ((v - edge0) / (edge1 - edge0)).clamp(0., 1.);
+    Ok(to_value(t * t * (3. - 2. * t))?)
+}
+
+pub fn pi(_: &HashMap<String, Value>) -> tera::Result<Value> {
+    Ok(to_value(PI)?)
+}
+
+pub fn normalize(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let v = get_required_vector_arg(args, "normalize", "v")?;
+
+    if v.near_zero() {
+        return Err(tera::Error::msg(
+            "Function `normalize` was called with a zero length `v` argument",
+        ));
+    }
+
+    Ok(vector_to_value(v.unit()))
+}
+
+pub fn cross(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let a = get_required_vector_arg(args, "cross", "a")?;
+    let b = get_required_vector_arg(args, "cross", "b")?;
+    Ok(vector_to_value(a.cross(b)))
+}
+
+pub fn dot(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let a = get_required_vector_arg(args, "dot", "a")?;
+    let b = get_required_vector_arg(args, "dot", "b")?;
+    Ok(to_value(a.dot(b))?)
+}
+
+pub fn hsv_to_rgb(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let h = get_required_numeric_arg(args, "hsv_to_rgb", "h")?;
+    let s = get_required_numeric_arg(args, "hsv_to_rgb", "s")?;
+    let v = get_required_numeric_arg(args, "hsv_to_rgb", "v")?;
+
+    let h = h.rem_euclid(360.) / 60.;
+    let c = v * s;
+    let x = c * (1. - (h % 2. - 1.).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (c, x, 0.),
+        1 => (x, c, 0.),
+        2 => (0., c, x),
+        3 => (0., x, c),
+        4 => (x, 0., c),
+        _ => (c, 0., x),
+    };
+    let m = v - c;
+
+    Ok(vector_to_value(Vec3::new(r + m, g + m, b + m)))
+}
+
+pub fn mix(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let a = get_required_vector_arg(args, "mix", "a")?;
+    let b = get_required_vector_arg(args, "mix", "b")?;
+    let t = get_required_numeric_arg(args, "mix", "t")?;
+    Ok(vector_to_value(a + (b - a) * t))
+}
+
+pub fn random(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let seed = get_required_numeric_arg(args, "random", "seed")?;
+    let min = get_optional_numeric_arg(args, "random", "min", 0.)?;
+    let max = get_optional_numeric_arg(args, "random", "max", 1.)?;
+
+    if min > max {
+        return Err(tera::Error::msg(
+            "Function `random` was called with a `min` argument greater than the `max` one",
+        ));
+    }
+
+    Ok(to_value(
+        min + (max - min) * SeededRandom::new(seed).next_f64(),
+    )?)
+}
+
+pub fn random_int(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let seed = get_required_numeric_arg(args, "random_int", "seed")?;
+    let min = get_optional_numeric_arg(args, "random_int", "min", 0.)?.ceil();
+    let max = get_required_numeric_arg(args, "random_int", "max")?.floor();
+
+    if min > max {
+        return Err(tera::Error::msg(
+            "Function `random_int` was called with a `min` argument greater than the `max` one",
+        ));
+    }
+
+    let v = min + ((max - min + 1.) * SeededRandom::new(seed).next_f64()).floor();
+    Ok(to_value(v.min(max) as i64)?)
+}
+
+pub fn random_in_sphere(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let seed = get_required_numeric_arg(args, "random_in_sphere", "seed")?;
+    let radius = get_optional_numeric_arg(args, "random_in_sphere", "radius", 1.)?;
+
+    Ok(vector_to_value(
+        SeededRandom::new(seed).next_in_unit_sphere() * radius,
+    ))
+}
+
+pub fn random_on_hemisphere(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let seed = get_required_numeric_arg(args, "random_on_hemisphere", "seed")?;
+    let normal = match args.get("normal") {
+        Some(val) => value_to_vector("random_on_hemisphere", "normal", val)?,
+        None => Vec3::new(0., 1., 0.),
+    };
+
+    if normal.near_zero() {
+        return Err(tera::Error::msg(
+            "Function `random_on_hemisphere` was called with a zero length `normal` argument",
+        ));
+    }
+
+    let mut rng = SeededRandom::new(seed);
+    let mut v = rng.next_in_unit_sphere();
+    while v.near_zero() {
+        v = rng.next_in_unit_sphere();
+    }
+    let v = v.unit();
+
+    Ok(vector_to_value(if v.dot(normal) < 0. {
+        v.neg()
+    } else {
+        v
+    }))
+}
+
+pub fn noise(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let x = get_optional_numeric_arg(args, "noise", "x", 0.)?;
+    let y = get_optional_numeric_arg(args, "noise", "y", 0.)?;
+    let z = get_optional_numeric_arg(args, "noise", "z", 0.)?;
+    let seed = get_optional_numeric_arg(args, "noise", "seed", 0.)?;
+
+    Ok(to_value(perlin_noise(x, y, z, seed))?)
+}
+
+/// Small deterministic random generator, so that templates render identically every time
+/// for the same arguments. Based on the SplitMix64 algorithm
+struct SeededRandom {
+    state: u64,
+}
+
+impl SeededRandom {
+    fn new(seed: f64) -> Self {
+        // Adding zero normalizes -0.0 to 0.0 so both give the same sequence
+        Self {
+            state: (seed + 0.).to_bits(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        mix_bits(self.state)
+    }
+
+    /// Random value in the range [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn next_in_unit_sphere(&mut self) -> Vec3 {
+        loop {
+            let v = Vec3::new(
+                self.next_f64() * 2. - 1.,
+                self.next_f64() * 2. - 1.,
+                self.next_f64() * 2. - 1.,
+            );
+            if v.length_squared() < 1. {
+                return v;
+            }
+        }
+    }
+}
+
+fn mix_bits(v: u64) -> u64 {
+    let mut z = v;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+/// Classic Perlin gradient noise, where the permutation table is replaced by hashing the
+/// lattice coordinates together with the seed. Returns a value roughly in the range [-1, 1]
+fn perlin_noise(x: f64, y: f64, z: f64, seed: f64) -> f64 {
+    let (xf, yf, zf) = (x.floor(), y.floor(), z.floor());
+    let (dx, dy, dz) = (x - xf, y - yf, z - zf);
+    let seed_hash = mix_bits((seed + 0.).to_bits());
+
+    let gradient = |ix: f64, iy: f64, iz: f64, px: f64, py: f64, pz: f64| {
+        let h = mix_bits(
+            seed_hash
+                ^ mix_bits(ix as i64 as u64)
+                ^ mix_bits((iy as i64 as u64).rotate_left(21))
+                ^ mix_bits((iz as i64 as u64).rotate_left(42)),
+        );
+        // The 12 edge directions of a cube, as in Ken Perlin's improved noise
+        match h % 12 {
+            0 => px + py,
+            1 => -px + py,
+            2 => px - py,
+            3 => -px - py,
+            4 => px + pz,
+            5 => -px + pz,
+            6 => px - pz,
+            7 => -px - pz,
+            8 => py + pz,
+            9 => -py + pz,
+            10 => py - pz,
+            _ => -py - pz,
+        }
+    };
+    let fade = |t: f64| t * t * t * (t * (t * 6. - 15.) + 10.);
+    let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+
+    let (u, v, w) = (fade(dx), fade(dy), fade(dz));
+
+    let x00 = lerp(
+        gradient(xf, yf, zf, dx, dy, dz),
+        gradient(xf + 1., yf, zf, dx - 1., dy, dz),
+        u,
+    );
+    let x10 = lerp(
+        gradient(xf, yf + 1., zf, dx, dy - 1., dz),
+        gradient(xf + 1., yf + 1., zf, dx - 1., dy - 1., dz),
+        u,
+    );
+    let x01 = lerp(
+        gradient(xf, yf, zf + 1., dx, dy, dz - 1.),
+        gradient(xf + 1., yf, zf + 1., dx - 1., dy, dz - 1.),
+        u,
+    );
+    let x11 = lerp(
+        gradient(xf, yf + 1., zf + 1., dx, dy - 1., dz - 1.),
+        gradient(xf + 1., yf + 1., zf + 1., dx - 1., dy - 1., dz - 1.),
+        u,
+    );
+
+    lerp(lerp(x00, x10, v), lerp(x01, x11, v), w)
+}
+
 fn get_required_numeric_arg(
     args: &HashMap<String, Value>,
     fn_name: &str,
@@ -111,3 +501,254 @@ fn value_to_numeric(fn_name: &str, arg_name: &str, val: &Value) -> tera::Result<
         ))),
     }
 }
+
+fn get_required_vector_arg(
+    args: &HashMap<String, Value>,
+    fn_name: &str,
+    arg_name: &str,
+) -> tera::Result<Vec3> {
+    match args.get(arg_name) {
+        Some(val) => value_to_vector(fn_name, arg_name, val),
+        None => Err(tera::Error::msg(format!(
+            "Function `{fn_name}` was called without a `{arg_name}` argument"
+        ))),
+    }
+}
+
+fn value_to_vector(fn_name: &str, arg_name: &str, val: &Value) -> tera::Result<Vec3> {
+    let err = || {
+        tera::Error::msg(format!(
+            "Function `{fn_name}` received {arg_name}={val} but `{arg_name}` can only be a vector like \"1, 2, 3\""
+        ))
+    };
+
+    let s = val.as_str().ok_or_else(err)?;
+    let parts: Vec<f64> = s
+        .split(',')
+        .map(|p| p.trim().parse::<f64>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| err())?;
+
+    match parts[..] {
+        [x, y, z] => Ok(Vec3::new(x, y, z)),
+        _ => Err(err()),
+    }
+}
+
+/// Vectors are returned in the same "x, y, z" format that is used for positions and colors
+/// in the scene, so that the result can be used directly as a value
+fn vector_to_value(v: Vec3) -> Value {
+    Value::String(format!("{}, {}, {}", v.x, v.y, v.z))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::RenderMode;
+
+    fn args(values: &[(&str, Value)]) -> HashMap<String, Value> {
+        values
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect()
+    }
+
+    fn num(v: Value) -> f64 {
+        v.as_f64().unwrap()
+    }
+
+    #[test]
+    fn math_functions() {
+        assert_eq!(
+            1.,
+            num(tan(&args(&[("v", to_value(PI / 4.).unwrap())])).unwrap()).round()
+        );
+        assert_eq!(
+            PI / 2.,
+            num(atan2(&args(&[("y", Value::from(1)), ("x", Value::from(0))])).unwrap())
+        );
+        assert_eq!(
+            8.,
+            num(pow(&args(&[("v", Value::from(2)), ("exp", Value::from(3))])).unwrap())
+        );
+        assert!(pow(&args(&[("v", Value::from(-8)), ("exp", Value::from(0.5))])).is_err());
+        assert!(pow(&args(&[("v", Value::from(0)), ("exp", Value::from(-1))])).is_err());
+        assert_eq!(1., num(exp(&args(&[("v", Value::from(0))])).unwrap()));
+        assert_eq!(0., num(ln(&args(&[("v", Value::from(1))])).unwrap()));
+        assert!(ln(&args(&[("v", Value::from(0))])).is_err());
+        assert_eq!(1., num(floor(&args(&[("v", Value::from(1.7))])).unwrap()));
+        assert_eq!(2., num(ceil(&args(&[("v", Value::from(1.2))])).unwrap()));
+        assert_eq!(2., num(round(&args(&[("v", Value::from(1.5))])).unwrap()));
+        assert_eq!(PI, num(pi(&args(&[])).unwrap()));
+    }
+
+    #[test]
+    fn range_functions() {
+        let a_b = |a: f64, b: f64| args(&[("a", Value::from(a)), ("b", Value::from(b))]);
+        assert_eq!(1., num(min(&a_b(1., 2.)).unwrap()));
+        assert_eq!(2., num(max(&a_b(1., 2.)).unwrap()));
+        assert!(min(&args(&[("a", Value::from(1))])).is_err());
+
+        let clamp_args = |v: f64, min: f64, max: f64| {
+            args(&[
+                ("v", Value::from(v)),
+                ("min", Value::from(min)),
+                ("max", Value::from(max)),
+            ])
+        };
+        assert_eq!(1., num(clamp(&clamp_args(3., 0., 1.)).unwrap()));
+        assert!(clamp(&clamp_args(3., 1., 0.)).is_err());
+
+        let mut lerp_args = a_b(2., 4.);
+        lerp_args.insert("t".to_string(), Value::from(0.25));
+        assert_eq!(2.5, num(lerp(&lerp_args).unwrap()));
+
+        let smoothstep_args = |v: f64| {
+            args(&[
+                ("edge0", Value::from(0)),
+                ("edge1", Value::from(2)),
+                ("v", Value::from(v)),
+            ])
+        };
+        assert_eq!(0., num(smoothstep(&smoothstep_args(-1.)).unwrap()));
+        assert_eq!(0.5, num(smoothstep(&smoothstep_args(1.)).unwrap()));
+        assert_eq!(1., num(smoothstep(&smoothstep_args(3.)).unwrap()));
+    }
+
+    #[test]
+    fn vector_functions() {
+        assert_eq!(
+            Value::from("0, 0.6, 0.8"),
+            normalize(&args(&[("v", Value::from("0, 3, 4"))])).unwrap()
+        );
+        assert!(normalize(&args(&[("v", Value::from("0, 0, 0"))])).is_err());
+        assert!(normalize(&args(&[("v", Value::from("1, 2"))])).is_err());
+        assert!(normalize(&args(&[("v", Value::from(1))])).is_err());
+
+        let a_b = args(&[("a", Value::from("1, 0, 0")), ("b", Value::from("0, 1, 0"))]);
+        assert_eq!(Value::from("0, 0, 1"), cross(&a_b).unwrap());
+        assert_eq!(0., num(dot(&a_b).unwrap()));
+    }
+
+    #[test]
+    fn color_functions() {
+        let hsv = |h: f64| {
+            args(&[
+                ("h", Value::from(h)),
+                ("s", Value::from(1)),
+                ("v", Value::from(1)),
+            ])
+        };
+        assert_eq!(Value::from("1, 0, 0"), hsv_to_rgb(&hsv(0.)).unwrap());
+        assert_eq!(Value::from("0, 1, 0"), hsv_to_rgb(&hsv(120.)).unwrap());
+        assert_eq!(Value::from("0, 0, 1"), hsv_to_rgb(&hsv(240.)).unwrap());
+        assert_eq!(Value::from("1, 0, 0"), hsv_to_rgb(&hsv(360.)).unwrap());
+
+        assert_eq!(
+            Value::from("0.5, 0.5, 0"),
+            mix(&args(&[
+                ("a", Value::from("1, 0, 0")),
+                ("b", Value::from("0, 1, 0")),
+                ("t", Value::from(0.5)),
+            ]))
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn random_functions() {
+        let random_args = |seed: f64| {
+            args(&[
+                ("seed", Value::from(seed)),
+                ("min", Value::from(-2)),
+                ("max", Value::from(2)),
+            ])
+        };
+        for seed in 0..100 {
+            let v = num(random(&random_args(seed as f64)).unwrap());
+            assert!((-2. ..2.).contains(&v));
+            assert_eq!(v, num(random(&random_args(seed as f64)).unwrap()));
+
+            let i = random_int(&random_args(seed as f64))
+                .unwrap()
+                .as_i64()
+                .unwrap();
+            assert!((-2..=2).contains(&i));
+
+            let seed_args = args(&[("seed", Value::from(seed))]);
+            let v = value_to_vector("", "", &random_in_sphere(&seed_args).unwrap()).unwrap();
+            assert!(v.length() < 1.);
+
+            let hemisphere_args = args(&[
+                ("seed", Value::from(seed)),
+                ("normal", Value::from("0, 0, -1")),
+            ]);
+            let v =
+                value_to_vector("", "", &random_on_hemisphere(&hemisphere_args).unwrap()).unwrap();
+            assert!((v.length() - 1.).abs() < 1e-9);
+            assert!(v.z <= 0.);
+        }
+        assert_ne!(
+            random(&random_args(1.)).unwrap(),
+            random(&random_args(2.)).unwrap()
+        );
+        assert!(random(&args(&[("min", Value::from(0))])).is_err());
+    }
+
+    #[test]
+    fn noise_function() {
+        let noise_args = |x: f64, seed: f64| {
+            args(&[
+                ("x", Value::from(x)),
+                ("y", Value::from(0.3)),
+                ("z", Value::from(-1.7)),
+                ("seed", Value::from(seed)),
+            ])
+        };
+
+        // Noise is always zero at the integer lattice points
+        let lattice_args = args(&[("x", Value::from(1)), ("y", Value::from(2))]);
+        assert_eq!(0., num(noise(&lattice_args).unwrap()));
+        for i in 0..100 {
+            let x = i as f64 * 0.37;
+            let v = num(noise(&noise_args(x, 3.)).unwrap());
+            assert!((-1.5..=1.5).contains(&v));
+            assert_eq!(v, num(noise(&noise_args(x, 3.)).unwrap()));
+        }
+        assert_ne!(
+            noise(&noise_args(0.5, 1.)).unwrap(),
+            noise(&noise_args(0.5, 2.)).unwrap()
+        );
+    }
+
+    #[test]
+    fn template() {
+        let yaml = apply_template(
+            "center: {{ normalize(v=\"0, 0, 2\") }}\nradius: {{ clamp(v=frameIndex, min=1, max=2) }}",
+            &TemplateContext {
+                frame_index: 5,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!("center: 0, 0, 1\nradius: 2", yaml);
+    }
+
+    #[test]
+    fn template_context() {
+        let yaml = apply_template(
+            "{{ frameIndex }} {{ frameCount }} {{ time }} {{ normalizedTime }} {{ width }}x{{ height }} {{ mode }} {{ spread }}",
+            &TemplateContext {
+                frame_index: 15,
+                frame_count: 61,
+                fps: 30.,
+                width: 800,
+                height: 600,
+                mode: RenderMode::Batch,
+                variables: vec!["spread=2.5".parse().unwrap()],
+            },
+        )
+        .unwrap();
+        assert_eq!("15 61 0.5 0.25 800x600 batch 2.5", yaml);
+    }
+}
@@ -7,6 +7,7 @@ use serde::{Deserialize, Serialize};
 use solstrale::geo::transformation::{
     RotationX, RotationY, RotationZ, Scale, Transformations, Transformer, Translation,
 };
+use solstrale::geo::vec3::Vec3;
 use std::collections::HashMap;
 use std::error::Error;
 
@@ -27,6 +28,12 @@ pub struct Transformation {
 
 impl Creator<Box<dyn Transformer>> for Transformation {
     fn create(&self, _: &CreatorContext) -> Result<Box<dyn Transformer>, Box<dyn Error>> {
+        self.transformer()
+    }
+}
+
+impl Transformation {
+    fn transformer(&self) -> Result<Box<dyn Transformer>, Box<dyn Error>> {
         match self {
             Transformation {
                 translation: Some(p),
@@ -70,6 +77,200 @@ impl Creator<Box<dyn Transformer>> for Transformation {
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    pub const ALL: [Axis; 3] = [Axis::X, Axis::Y, Axis::Z];
+
+    pub fn unit(&self) -> Vec3 {
+        match self {
+            Axis::X => Vec3::new(1., 0., 0.),
+            Axis::Y => Vec3::new(0., 1., 0.),
+            Axis::Z => Vec3::new(0., 0., 1.),
+        }
+    }
+}
+
+/// Moves by the given offset, merged into a translation at the end of the list if there is one
+pub fn translate(transformations: &mut Vec<Transformation>, delta: Vec3) {
+    transformations.push(Transformation {
+        translation: Some(delta.into()),
+        ..Default::default()
+    });
+    simplify(transformations);
+}
+
+/// Rotates around the axis going through the pivot point
+pub fn rotate(transformations: &mut Vec<Transformation>, axis: Axis, degrees: f64, pivot: Vec3) {
+    let rotation = match axis {
+        Axis::X => Transformation {
+            rotation_x: Some(degrees),
+            ..Default::default()
+        },
+        Axis::Y => Transformation {
+            rotation_y: Some(degrees),
+            ..Default::default()
+        },
+        Axis::Z => Transformation {
+            rotation_z: Some(degrees),
+            ..Default::default()
+        },
+    };
+    around_pivot(transformations, rotation, pivot);
+}
+
+/// Scales uniformly around the pivot point
+pub fn scale(transformations: &mut Vec<Transformation>, factor: f64, pivot: Vec3) {
+    let scale = Transformation {
+        scale: Some(factor),
+        ..Default::default()
+    };
+    around_pivot(transformations, scale, pivot);
+}
+
+fn around_pivot(transformations: &mut Vec<Transformation>, t: Transformation, pivot: Vec3) {
+    transformations.push(Transformation {
+        translation: Some(pivot.neg().into()),
+        ..Default::default()
+    });
+    transformations.push(t);
+    transformations.push(Transformation {
+        translation: Some(pivot.into()),
+        ..Default::default()
+    });
+    simplify(transformations);
+}
+
+/// Merges neighbouring transformations of the same kind and removes those that do nothing, so
+/// that repeated changes, like dragging an object around, keep the list short
+fn simplify(transformations: &mut Vec<Transformation>) {
+    let mut simplified: Vec<Transformation> = Vec::with_capacity(transformations.len());
+
+    for t in transformations.drain(..) {
+        let merged = match (simplified.last(), &t) {
+            (
+                Some(Transformation {
+                    translation: Some(a),
+                    ..
+                }),
+                Transformation {
+                    translation: Some(b),
+                    ..
+                },
+            ) => Some(Transformation {
+                translation: Some(Pos::new(
+                    round(a.x + b.x),
+                    round(a.y + b.y),
+                    round(a.z + b.z),
+                )),
+                ..Default::default()
+            }),
+            (
+                Some(Transformation { scale: Some(a), .. }),
+                Transformation { scale: Some(b), .. },
+            ) => Some(Transformation {
+                scale: Some(round(a * b)),
+                ..Default::default()
+            }),
+            (
+                Some(Transformation {
+                    rotation_x: Some(a),
+                    ..
+                }),
+                Transformation {
+                    rotation_x: Some(b),
+                    ..
+                },
+            ) => Some(Transformation {
+                rotation_x: Some(round(a + b)),
+                ..Default::default()
+            }),
+            (
+                Some(Transformation {
+                    rotation_y: Some(a),
+                    ..
+                }),
+                Transformation {
+                    rotation_y: Some(b),
+                    ..
+                },
+            ) => Some(Transformation {
+                rotation_y: Some(round(a + b)),
+                ..Default::default()
+            }),
+            (
+                Some(Transformation {
+                    rotation_z: Some(a),
+                    ..
+                }),
+                Transformation {
+                    rotation_z: Some(b),
+                    ..
+                },
+            ) => Some(Transformation {
+                rotation_z: Some(round(a + b)),
+                ..Default::default()
+            }),
+            _ => None,
+        };
+
+        match merged {
+            Some(m) => {
+                simplified.pop();
+                if !m.is_identity() {
+                    simplified.push(m);
+                }
+            }
+            None => simplified.push(t),
+        }
+    }
+    *transformations = simplified;
+}
+
+impl Transformation {
+    fn is_identity(&self) -> bool {
+        match self {
+            Transformation {
+                translation: Some(p),
+                ..
+            } => p.x == 0. && p.y == 0. && p.z == 0.,
+            Transformation { scale: Some(s), .. } => *s == 1.,
+            Transformation {
+                rotation_x: Some(r),
+                ..
+            }
+            | Transformation {
+                rotation_y: Some(r),
+                ..
+            }
+            | Transformation {
+                rotation_z: Some(r),
+                ..
+            } => *r == 0.,
+            _ => false,
+        }
+    }
+}
+
+/// Rounding keeps the values readable when written to the scene yaml
+fn round(v: f64) -> f64 {
+    (v * 1_000_000.).round() / 1_000_000. + 0.
+}
+
+/// Same as [`create_transformation`], for use outside of creating the scene for the renderer
+pub fn transformer(transformations: &[Transformation]) -> Result<Transformations, Box<dyn Error>> {
+    let mut trans: Vec<Box<dyn Transformer>> = Vec::with_capacity(transformations.len());
+    for t in transformations {
+        trans.push(t.transformer()?);
+    }
+    Ok(Transformations::new(trans))
+}
+
 pub fn create_transformation(
     transformations: &Vec<Transformation>,
     ctx: &CreatorContext,
@@ -130,3 +331,47 @@ impl HelpDocumentation for Transformation {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn drag_transformations() {
+        let mut transformations = vec![Transformation {
+            translation: Some(Pos::new(1., 0., 0.)),
+            ..Default::default()
+        }];
+
+        translate(&mut transformations, Vec3::new(0.1, 0.2, 0.));
+        translate(&mut transformations, Vec3::new(0.2, 0., 0.));
+        assert_eq!(
+            vec![Transformation {
+                translation: Some(Pos::new(1.3, 0.2, 0.)),
+                ..Default::default()
+            }],
+            transformations
+        );
+
+        let pivot = Vec3::new(1.3, 0.2, 0.);
+        rotate(&mut transformations, Axis::Y, 10., pivot);
+        rotate(&mut transformations, Axis::Y, 20., pivot);
+        assert_eq!(
+            vec![
+                Transformation {
+                    rotation_y: Some(30.),
+                    ..Default::default()
+                },
+                Transformation {
+                    translation: Some(Pos::new(1.3, 0.2, 0.)),
+                    ..Default::default()
+                },
+            ],
+            transformations
+        );
+
+        scale(&mut transformations, 2., pivot);
+        scale(&mut transformations, 0.5, pivot);
+        assert_eq!(2, transformations.len());
+    }
+}
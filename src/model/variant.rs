@@ -0,0 +1,114 @@
+use std::error::Error;
+
+use serde_yaml::Value;
+
+use crate::model::ModelError;
+
+static VARIANTS: &str = "variants";
+
+/// Overlays the named variant from the `variants` section onto the rest of the scene document.
+/// Maps are merged recursively while all other values, including lists, are replaced
+pub fn apply_variant(document: &mut Value, name: &str) -> Result<(), Box<dyn Error>> {
+    let overlay = document
+        .get(VARIANTS)
+        .and_then(|variants| variants.get(name))
+        .cloned()
+        .ok_or_else(|| unknown_variant_error(document, name))?;
+
+    if let Value::Mapping(overlay) = overlay {
+        for (key, value) in overlay {
+            if key.as_str() == Some(VARIANTS) {
+                continue;
+            }
+            match document.get_mut(&key) {
+                Some(existing) => merge(existing, value),
+                None => {
+                    if let Some(mapping) = document.as_mapping_mut() {
+                        mapping.insert(key, value);
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn merge(target: &mut Value, overlay: Value) {
+    match (target, overlay) {
+        (Value::Mapping(target), Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                match target.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (target, overlay) => *target = overlay,
+    }
+}
+
+fn unknown_variant_error(document: &Value, name: &str) -> ModelError {
+    let available: Vec<&str> = document
+        .get(VARIANTS)
+        .and_then(Value::as_mapping)
+        .map(|variants| variants.keys().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+
+    if available.is_empty() {
+        ModelError::new(&format!(
+            "Unknown variant '{name}', the scene has no variants"
+        ))
+    } else {
+        ModelError::new(&format!(
+            "Unknown variant '{name}', available variants are: {}",
+            available.join(", ")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn apply() {
+        let mut document: Value = serde_yaml::from_str(
+            "render_configuration:
+  samples_per_pixel: 200
+  post_processors:
+  - bloom: {}
+camera:
+  look_from: 0, 0, 0
+variants:
+  draft:
+    render_configuration:
+      samples_per_pixel: 10
+      post_processors: []
+",
+        )
+        .unwrap();
+
+        apply_variant(&mut document, "draft").unwrap();
+        assert_eq!(
+            Some(&Value::from(10)),
+            document["render_configuration"].get("samples_per_pixel")
+        );
+        assert_eq!(
+            Some(&Value::Sequence(vec![])),
+            document["render_configuration"].get("post_processors")
+        );
+        assert_eq!(
+            Some(&Value::from("0, 0, 0")),
+            document["camera"].get("look_from")
+        );
+
+        assert_eq!(
+            "Unknown variant 'final', available variants are: draft",
+            apply_variant(&mut document, "final")
+                .unwrap_err()
+                .to_string()
+        );
+    }
+}
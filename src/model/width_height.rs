@@ -38,31 +38,42 @@ impl Default for WidthHeight {
 
 impl Creator<(usize, usize)> for WidthHeight {
     fn create(&self, ctx: &CreatorContext) -> Result<(usize, usize), Box<dyn Error>> {
+        self.size(ctx.screen_width, ctx.screen_height)
+    }
+}
+
+impl WidthHeight {
+    /// Width and height of the rendered image for the given size of the screen
+    pub fn size(
+        &self,
+        screen_width: usize,
+        screen_height: usize,
+    ) -> Result<(usize, usize), Box<dyn Error>> {
         match self {
             WidthHeight {
                 screen: Some(s),
                 half_screen: None,
                 quarter_screen: None,
                 custom: None,
-            } => s.create(ctx),
+            } => Ok(s.size(screen_width, screen_height)),
             WidthHeight {
                 screen: None,
                 half_screen: Some(s),
                 quarter_screen: None,
                 custom: None,
-            } => s.create(ctx),
+            } => Ok(s.size(screen_width, screen_height)),
             WidthHeight {
                 screen: None,
                 half_screen: None,
                 quarter_screen: Some(s),
                 custom: None,
-            } => s.create(ctx),
+            } => Ok(s.size(screen_width, screen_height)),
             WidthHeight {
                 screen: None,
                 half_screen: None,
                 quarter_screen: None,
                 custom: Some(s),
-            } => s.create(ctx),
+            } => s.size(),
             _ => Err(From::from(ModelError::new(
                 "WidthHeight should have single field defined",
             ))),
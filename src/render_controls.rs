@@ -0,0 +1,272 @@
+use std::time::Duration;
+
+use eframe::egui::{Button, DragValue, Ui};
+use hhmmss::Hhmmss;
+
+use crate::{RenderControl, RenderedImage};
+
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub enum RenderState {
+    #[default]
+    Rendering,
+    /// The renderer is stopped, and continues from the samples shown when resumed
+    Paused,
+    /// The renderer is stopped and the image shown is final, until rendering again
+    Stopped,
+}
+
+/// Limits after which rendering stops, before all the samples per pixel of the scene are
+/// rendered
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub struct RenderTarget {
+    pub max_samples: Option<u32>,
+    pub max_seconds: Option<f64>,
+}
+
+/// Stops the renderer, keeping the image shown
+fn abort(render_control: &mut RenderControl) {
+    if let Some(sender) = &render_control.abort_sender {
+        sender.send(true).ok();
+    }
+    render_control.abort_sender = None;
+    render_control.render_receiver = None;
+    render_control.camera_config_sender = None;
+}
+
+pub fn pause(render_control: &mut RenderControl, time: f64) {
+    abort(render_control);
+    render_control.render_seconds = elapsed_seconds(render_control, time);
+    render_control.render_state = RenderState::Paused;
+}
+
+/// The renderer can not continue from an image, so it renders again from the first sample.
+/// Samples are the same every time, so the paused image is shown until the renderer has
+/// caught up with it
+pub fn resume(render_control: &mut RenderControl, rendered_image: &RenderedImage) {
+    render_control.resumed_samples = rendered_image.samples;
+    render_control.render_region.request_resume();
+    render_control.resume_requested = true;
+    render_control.scene_updated = true;
+    render_control.render_requested = true;
+}
+
+pub fn stop(render_control: &mut RenderControl, time: f64) {
+    if render_control.render_state == RenderState::Rendering {
+        render_control.render_seconds = elapsed_seconds(render_control, time);
+    }
+    abort(render_control);
+    render_control.render_state = RenderState::Stopped;
+}
+
+/// Called when the renderer is started, either for a new image or to resume a paused one
+pub fn started(render_control: &mut RenderControl, time: f64) {
+    if !render_control.resume_requested {
+        render_control.resumed_samples = 0;
+        render_control.render_seconds = 0.;
+    }
+    render_control.resume_requested = false;
+    render_control.render_state = RenderState::Rendering;
+    render_control.render_started_at = time;
+}
+
+/// Called when a resumed render has caught up with the paused image. The time spent rendering
+/// the samples again is not counted, since it was counted before pausing
+pub fn caught_up(render_control: &mut RenderControl, time: f64) {
+    render_control.resumed_samples = 0;
+    render_control.render_started_at = time;
+}
+
+/// Called when the renderer starts over from the first sample for a new camera position
+pub fn restarted(render_control: &mut RenderControl, time: f64) {
+    render_control.resumed_samples = 0;
+    render_control.render_seconds = 0.;
+    render_control.render_started_at = time;
+}
+
+/// Seconds spent rendering the image, without the time paused or catching up after resuming
+pub fn elapsed_seconds(render_control: &RenderControl, time: f64) -> f64 {
+    match render_control.render_state {
+        RenderState::Rendering if render_control.resumed_samples > 0 => {
+            render_control.render_seconds
+        }
+        RenderState::Rendering => {
+            render_control.render_seconds + time - render_control.render_started_at
+        }
+        RenderState::Paused | RenderState::Stopped => render_control.render_seconds,
+    }
+}
+
+fn is_finished(render_control: &RenderControl, rendered_image: &RenderedImage) -> bool {
+    render_control.samples_per_pixel > 0
+        && rendered_image.samples >= render_control.samples_per_pixel
+}
+
+/// Stops rendering when the render target is reached
+pub fn check_target(ui: &Ui, render_control: &mut RenderControl, rendered_image: &RenderedImage) {
+    if render_control.render_state != RenderState::Rendering
+        || render_control.render_receiver.is_none()
+        || is_finished(render_control, rendered_image)
+    {
+        return;
+    }
+
+    let time = ui.input(|i| i.time);
+    let target = render_control.render_target;
+    if target
+        .max_samples
+        .is_some_and(|max| rendered_image.samples >= max)
+    {
+        stop(render_control, time);
+    } else if let Some(max) = target.max_seconds {
+        let left = max - elapsed_seconds(render_control, time);
+        if left <= 0. {
+            stop(render_control, time);
+        } else {
+            ui.ctx()
+                .request_repaint_after(Duration::from_secs_f64(left));
+        }
+    }
+}
+
+/// Text of the progress bar, with the samples rendered and the render target
+pub fn progress_text(
+    render_control: &RenderControl,
+    rendered_image: &RenderedImage,
+    time: f64,
+) -> String {
+    let mut text = format!(
+        "{:.0}% {}/{} samples",
+        rendered_image.progress * 100.,
+        rendered_image.samples,
+        render_control.samples_per_pixel,
+    );
+    let target = render_control.render_target;
+    if let Some(max) = target.max_samples {
+        text += &format!(" (stop at {max})");
+    }
+    if let Some(max) = target.max_seconds {
+        text += &format!(
+            " {}/{}",
+            Duration::from_secs_f64(elapsed_seconds(render_control, time)).hhmmss(),
+            Duration::from_secs_f64(max).hhmmss()
+        );
+    }
+    match render_control.render_state {
+        RenderState::Paused => text += " Paused",
+        RenderState::Stopped => text += " Stopped",
+        RenderState::Rendering if render_control.resumed_samples > 0 => text += " Resuming",
+        RenderState::Rendering => {}
+    }
+    text
+}
+
+/// Buttons for pausing, resuming and stopping the render, and the menu of the render target
+pub fn show(ui: &mut Ui, render_control: &mut RenderControl, rendered_image: &RenderedImage) {
+    let time = ui.input(|i| i.time);
+    let rendering = render_control.render_state == RenderState::Rendering
+        && render_control.render_receiver.is_some()
+        && !is_finished(render_control, rendered_image);
+
+    if render_control.render_state == RenderState::Paused {
+        if ui
+            .button("Resume")
+            .on_hover_text("Continue rendering from the samples rendered so far")
+            .clicked()
+        {
+            resume(render_control, rendered_image);
+        }
+    } else if ui
+        .add_enabled(
+            rendering && rendered_image.samples > 0,
+            Button::new("Pause"),
+        )
+        .on_hover_text("Pause rendering, keeping the samples rendered so far")
+        .clicked()
+    {
+        pause(render_control, time);
+    }
+
+    if ui
+        .add_enabled(
+            rendering || render_control.render_state == RenderState::Paused,
+            Button::new("Stop"),
+        )
+        .on_hover_text("Stop rendering and keep the current image as final")
+        .clicked()
+    {
+        stop(render_control, time);
+    }
+
+    ui.menu_button("Target", |ui| {
+        let target = &mut render_control.render_target;
+        let mut limit_samples = target.max_samples.is_some();
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut limit_samples, "Samples");
+            let mut samples = target
+                .max_samples
+                .unwrap_or(render_control.samples_per_pixel);
+            ui.add_enabled(
+                limit_samples,
+                DragValue::new(&mut samples).range(1..=u32::MAX),
+            );
+            target.max_samples = limit_samples.then_some(samples.max(1));
+        })
+        .response
+        .on_hover_text("Stop rendering after this many samples per pixel");
+
+        let mut limit_time = target.max_seconds.is_some();
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut limit_time, "Time");
+            let mut seconds = target.max_seconds.unwrap_or(60.);
+            ui.add_enabled(
+                limit_time,
+                DragValue::new(&mut seconds)
+                    .speed(1.)
+                    .range(1.0..=f64::MAX)
+                    .suffix(" s"),
+            );
+            target.max_seconds = limit_time.then_some(seconds);
+        })
+        .response
+        .on_hover_text("Stop rendering after this time, not counting the time paused");
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn elapsed_without_pauses() {
+        let mut render_control = RenderControl::default();
+        let rendered_image = RenderedImage {
+            samples: 5,
+            ..Default::default()
+        };
+
+        started(&mut render_control, 10.);
+        assert_eq!(2., elapsed_seconds(&render_control, 12.));
+
+        pause(&mut render_control, 13.);
+        assert_eq!(RenderState::Paused, render_control.render_state);
+        assert_eq!(3., elapsed_seconds(&render_control, 20.));
+
+        resume(&mut render_control, &rendered_image);
+        started(&mut render_control, 20.);
+        assert_eq!(5, render_control.resumed_samples);
+        // Catching up with the paused image is not counted
+        assert_eq!(3., elapsed_seconds(&render_control, 21.));
+        caught_up(&mut render_control, 21.);
+        assert_eq!(0, render_control.resumed_samples);
+        assert_eq!(4., elapsed_seconds(&render_control, 22.));
+
+        stop(&mut render_control, 23.);
+        assert_eq!(RenderState::Stopped, render_control.render_state);
+        assert_eq!(5., elapsed_seconds(&render_control, 30.));
+
+        // A new render starts over
+        started(&mut render_control, 30.);
+        assert_eq!(0, render_control.resumed_samples);
+        assert_eq!(1., elapsed_seconds(&render_control, 31.));
+    }
+}
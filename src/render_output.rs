@@ -1,20 +1,42 @@
+use std::error::Error;
 use std::sync::mpsc::{Receiver, Sender, TryRecvError, channel};
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::Duration;
 
-use eframe::egui::{Context, PointerButton, Sense, Ui, Vec2};
+use bytemuck::Zeroable;
+use eframe::egui::{Context, Key, PointerButton, Rect, Response, Sense, Stroke, Ui, Vec2};
 use eframe::wgpu;
 use eframe::wgpu::util::DeviceExt;
+use solstrale::geo::transformation::NopTransformer;
 use solstrale::geo::vec3::Vec3;
 use solstrale::ray_trace;
 
-use crate::model::orbit_camera::OrbitCamera;
+use crate::camera_menu::frame_selected;
+use crate::camera_panel::CameraSettings;
+use crate::camera_yaml::list_item_line;
+use crate::display_settings::DISPLAY_SHADER;
+use crate::gizmo;
+use crate::model::fly_camera::FlyCamera;
+use crate::model::orbit_camera::{OrbitCamera, View};
+use crate::model::ray_cast::{BoundingBox, box_corners};
 use crate::model::scene::Scene;
-use crate::model::{Creator, CreatorContext, parse_scene_yaml};
+use crate::model::scene_override::SceneOverride;
+use crate::model::{
+    Creator, CreatorContext, RenderMode, TemplateContext, parse_scene_yaml_with_overrides,
+};
+use crate::render_controls::{self, RenderState};
+use crate::render_region::create_composite_pipeline;
+use crate::snapshots::RenderSource;
+use crate::yaml_editor::move_cursor_to_line;
 use crate::{
-    ErrorInfo, RenderCallback, RenderControl, RenderMessage, RenderResources, RenderedImage,
+    CameraMode, DisplayUniform, ErrorInfo, RenderCallback, RenderControl, RenderMessage,
+    RenderResources, RenderedImage,
 };
 
+/// Time the camera has to stand still before rendering at full resolution again
+const SETTLE_SECONDS: f64 = 0.3;
+
 const SHADER: &str = r#"
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
@@ -30,20 +52,37 @@ fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
     return out;
 }
 
-@group(0) @binding(0) var<uniform> viewport_size: vec2<f32>;
+struct Display {
+    // Size of the rendered image, which is stretched over the painted area when it is rendered
+    // at a lower resolution, like while the camera moves
+    image_size: vec2<f32>,
+    // Part of the image shown in the painted area, which is less than the whole image when
+    // zoomed in
+    uv_min: vec2<f32>,
+    uv_max: vec2<f32>,
+    // Display settings, see `DisplaySettings`
+    exposure: f32,
+    tone_mapping: u32,
+    gamma: f32,
+    channel: u32,
+};
+
+@group(0) @binding(0) var<uniform> display: Display;
 @group(0) @binding(1) var<storage, read> buffer: array<f32>;
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let x = min(u32(in.uv.x * viewport_size.x), u32(viewport_size.x) - 1u);
-    let y = min(u32(in.uv.y * viewport_size.y), u32(viewport_size.y) - 1u);
-    let index = (y * u32(viewport_size.x) + x) * 4u;
+    // Nearest neighbor sampling, so single pixels stay sharp when zoomed in
+    let uv = mix(display.uv_min, display.uv_max, in.uv);
+    let x = min(u32(uv.x * display.image_size.x), u32(display.image_size.x) - 1u);
+    let y = min(u32(uv.y * display.image_size.y), u32(display.image_size.y) - 1u);
+    let index = (y * u32(display.image_size.x) + x) * 4u;
 
     let r = buffer[index];
     let g = buffer[index + 1u];
     let b = buffer[index + 2u];
 
-    return vec4<f32>(r, g, b, 1.0);
+    return vec4<f32>(display_color(vec3<f32>(r, g, b)), 1.0);
 }
 "#;
 
@@ -54,7 +93,7 @@ pub fn create_render_resources(
 ) -> RenderResources {
     let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
         label: Some("Render Shader"),
-        source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        source: wgpu::ShaderSource::Wgsl(format!("{SHADER}{DISPLAY_SHADER}").into()),
     });
 
     let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -115,16 +154,20 @@ pub fn create_render_resources(
         cache: None,
     });
 
-    let viewport_size_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: Some("Viewport Size Buffer"),
-        contents: bytemuck::cast_slice(&[0.0f32, 0.0f32]),
+    let display_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Display Buffer"),
+        contents: bytemuck::bytes_of(&DisplayUniform::zeroed()),
         usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
     });
 
+    let (composite_pipeline, composite_bind_group_layout) = create_composite_pipeline(device);
+
     RenderResources {
         pipeline,
         bind_group_layout,
-        viewport_size_buffer,
+        display_buffer,
+        composite_pipeline,
+        composite_bind_group_layout,
         target_format,
         device: device.clone(),
         queue: queue.clone(),
@@ -136,23 +179,52 @@ pub fn render_output(
     render_control: &mut RenderControl,
     rendered_image: &mut RenderedImage,
     error_info: &mut ErrorInfo,
-    scene_yaml: &str,
+    scene_yaml: &mut String,
     viewport_size: Vec2,
 ) {
     // Process messages from the renderer
+    let mut caught_up = false;
     if let Some(render_receiver) = &render_control.render_receiver {
         loop {
             match render_receiver.try_recv() {
                 Ok(render_message) => match render_message {
+                    RenderMessage::Started(width, height, samples_per_pixel) => {
+                        render_control.samples_per_pixel = samples_per_pixel;
+                        // A region is shown in the image it is composited over
+                        if !render_control.render_region.is_active() {
+                            render_control.image_size = Vec2::new(width as f32, height as f32);
+                            if !render_control.reduced_resolution {
+                                render_control.full_image_size = render_control.image_size;
+                            }
+                        }
+                    }
                     RenderMessage::SampleRendered(render_progress) => {
-                        rendered_image.output_buffer =
-                            Some(Arc::new(render_progress.output_buffer));
+                        render_control.loading_scene = false;
+                        let samples = (render_progress.progress
+                            * render_control.samples_per_pixel as f64)
+                            .round() as u32;
+                        // A resumed render shows the paused image until it has caught up
+                        if samples < render_control.resumed_samples {
+                            continue;
+                        }
+                        caught_up |= render_control.resumed_samples > 0;
+                        rendered_image.samples = samples;
+                        if let Some(composited) = render_control.render_region.composite(
+                            rendered_image.render_resources.as_deref(),
+                            &render_progress.output_buffer,
+                        ) {
+                            rendered_image.output_buffer = Some(composited);
+                        } else {
+                            rendered_image.output_buffer =
+                                Some(Arc::new(render_progress.output_buffer));
+                            rendered_image.width = render_control.image_size.x as u32;
+                            rendered_image.height = render_control.image_size.y as u32;
+                        }
                         rendered_image.progress = render_progress.progress;
                         if let Some(fps) = render_progress.fps {
                             rendered_image.fps = fps;
                         }
                         rendered_image.estimated_time_left = render_progress.estimated_time_left;
-                        render_control.loading_scene = false;
                     }
                     RenderMessage::Error(error_message) => {
                         error_info.handle_str(&error_message);
@@ -172,67 +244,201 @@ pub fn render_output(
         }
     }
 
+    if caught_up {
+        render_controls::caught_up(render_control, ui.input(|i| i.time));
+    }
+    render_controls::check_target(ui, render_control, rendered_image);
+
     // UI and Interaction
     if viewport_size.x > 0.0 && viewport_size.y > 0.0 {
-        let (rect, response) = ui.allocate_exact_size(viewport_size, Sense::drag());
+        let (viewport, response) = ui.allocate_exact_size(viewport_size, Sense::click_and_drag());
+        let comparing = render_control.snapshots.is_comparing();
+        let rect = if comparing {
+            render_control.snapshots.image_rect(
+                viewport,
+                &render_control.image_view,
+                ui.ctx().pixels_per_point(),
+            )
+        } else {
+            render_control.image_view.image_rect(
+                viewport,
+                render_control.full_image_size,
+                ui.ctx().pixels_per_point(),
+            )
+        };
 
-        // Paint the last rendered image
-        if let (Some(resources), Some(output_buffer)) = (
+        // Paint the compared snapshots, or the visible part of the last rendered image
+        let visible = rect.intersect(viewport);
+        if comparing {
+            render_control.snapshots.show_comparison(
+                ui,
+                rect,
+                viewport,
+                &rendered_image.display_settings,
+            );
+        } else if let (Some(resources), Some(output_buffer)) = (
             &rendered_image.render_resources,
             &rendered_image.output_buffer,
         ) && output_buffer.size() > 0
+            && visible.is_positive()
         {
             ui.painter()
                 .add(eframe::egui_wgpu::Callback::new_paint_callback(
-                    rect,
+                    visible,
                     RenderCallback {
                         resources: resources.clone(),
                         output_buffer: output_buffer.clone(),
                         width: rendered_image.width,
                         height: rendered_image.height,
+                        display_settings: rendered_image.display_settings,
+                        uv_rect: Rect::from_min_max(
+                            ((visible.min - rect.min) / rect.size()).to_pos2(),
+                            ((visible.max - rect.min) / rect.size()).to_pos2(),
+                        ),
                         bind_group: Arc::new(Mutex::new(None)),
                     },
                 ));
         }
 
+        if render_control.image_view.is_inspecting() {
+            render_control.image_view.interact(ui, &response, rect);
+            if !comparing {
+                render_control
+                    .image_view
+                    .show_pixel(ui, &response, rect, viewport, rendered_image);
+            }
+        }
+
+        // Drawing a region to render, instead of controlling the camera
+        let selecting = render_control.render_region.selecting
+            && !render_control.image_view.is_inspecting()
+            && !comparing;
+        if selecting
+            && render_control
+                .render_region
+                .select(ui, &response, rect, viewport)
+        {
+            render_control.scene_updated = true;
+            render_control.render_requested = true;
+        }
+
+        // Snapshots are not of the current view, so the camera and scene are left alone
+        let inspecting = render_control.image_view.is_inspecting() || comparing || selecting;
+        if !inspecting
+            && response.clicked()
+            && let Some(pointer) = response.interact_pointer_pos()
+        {
+            let x = ((pointer.x - rect.min.x) / rect.width()) as f64;
+            let y = ((pointer.y - rect.min.y) / rect.height()) as f64;
+            let aspect_ratio = rect.aspect_ratio() as f64;
+            let res = if !rect.contains(pointer) {
+                Ok(())
+            } else if ui.input(|i| i.modifiers.command) {
+                focus_at(render_control, x, y, aspect_ratio)
+            } else {
+                pick_at(render_control, scene_yaml, x, y, aspect_ratio, ui.ctx())
+            };
+            if let Err(err) = res {
+                error_info.handle(err);
+            }
+        }
+
+        if !inspecting
+            && response.double_clicked()
+            && let Err(err) = frame_selected(render_control, rect.aspect_ratio() as f64)
+        {
+            error_info.handle(err);
+        }
+
+        if !inspecting && let Err(err) = highlight_selected_object(ui, render_control, rect) {
+            render_control.selected_object = None;
+            error_info.handle(err);
+        }
+        if !inspecting {
+            gizmo::show(ui, render_control, scene_yaml, rect, error_info);
+        }
+
         // Handle camera interactions
+        let mut camera_moved = false;
         if let Some(orbit_camera) = &mut render_control.orbit_camera {
+            let settings = &render_control.camera_settings;
             let mut input_changed = false;
-            if response.dragged_by(PointerButton::Primary) {
-                let delta = response.drag_delta();
-                if delta.x != 0.0 || delta.y != 0.0 {
-                    orbit_camera.orbit(-delta.x as f64 * 0.01, -delta.y as f64 * 0.01);
-                    input_changed = true;
+            match render_control.camera_mode {
+                _ if inspecting => {}
+                CameraMode::Orbit => {
+                    if response.dragged_by(PointerButton::Primary) {
+                        let delta = response.drag_delta();
+                        if delta.x != 0.0 || delta.y != 0.0 {
+                            orbit_camera.orbit(
+                                -delta.x as f64 * settings.orbit_sensitivity,
+                                -delta.y as f64 * settings.orbit_sensitivity,
+                            );
+                            input_changed = true;
+                        }
+                    }
+                    if response.dragged_by(PointerButton::Secondary)
+                        || response.dragged_by(PointerButton::Middle)
+                    {
+                        let delta = response.drag_delta();
+                        if delta.x != 0.0 || delta.y != 0.0 {
+                            orbit_camera.pan(
+                                delta.x as f64 * settings.pan_sensitivity,
+                                delta.y as f64 * settings.pan_sensitivity,
+                            );
+                            input_changed = true;
+                        }
+                    }
+                    let scroll = ui.input(|i| i.smooth_scroll_delta.y);
+                    if scroll != 0.0 {
+                        orbit_camera.zoom(-scroll as f64 * settings.zoom_sensitivity);
+                        input_changed = true;
+                    }
+                    input_changed |= orbit_camera_keys(ui, orbit_camera, settings);
                 }
-            }
-            if response.dragged_by(PointerButton::Secondary)
-                || response.dragged_by(PointerButton::Middle)
-            {
-                let delta = response.drag_delta();
-                if delta.x != 0.0 || delta.y != 0.0 {
-                    orbit_camera.pan(
-                        delta.x as f64 * 0.001 * orbit_camera.current_distance,
-                        delta.y as f64 * 0.001 * orbit_camera.current_distance,
-                        Vec3::new(0., 1., 0.),
-                    );
-                    input_changed = true;
+                CameraMode::Fly => {
+                    let fly_camera = render_control.fly_camera.get_or_insert_with(|| {
+                        FlyCamera::new(
+                            orbit_camera.look_from(),
+                            orbit_camera.look_at(),
+                            orbit_camera.damping_factor,
+                        )
+                    });
+                    input_changed = fly_camera_input(ui, &response, fly_camera);
+                    if fly_camera.update() || input_changed {
+                        orbit_camera.set_view(fly_camera.look_from(), fly_camera.look_at());
+                        input_changed = true;
+                    }
                 }
             }
-            let scroll = ui.input(|i| i.smooth_scroll_delta.y);
-            if scroll != 0.0 {
-                orbit_camera.zoom(-scroll as f64);
-                input_changed = true;
+
+            let time = ui.input(|i| i.time);
+            if let Some(next_sample) = render_control.camera_recorder.sample(time, orbit_camera) {
+                ui.ctx().request_repaint_after(next_sample);
             }
 
             if orbit_camera.update() || input_changed {
                 render_control.camera_updated = true;
+                render_control.last_camera_move = time;
+                camera_moved = true;
                 ui.ctx().request_repaint();
             }
         }
+
+        change_resolution_for_interaction(ui, render_control, camera_moved);
     }
 
     // Handle render restarts
 
+    // Changing the view of a paused or stopped render, or of a region, renders the full image
+    // again
+    if render_control.camera_updated
+        && (render_control.render_state != RenderState::Rendering
+            || render_control.render_region.is_active())
+    {
+        render_control.scene_updated = true;
+        render_control.render_requested = true;
+    }
+
     if render_control.camera_updated
         && let (Some(sender), Some(orbit_camera)) = (
             &render_control.camera_config_sender,
@@ -240,7 +446,9 @@ pub fn render_output(
         )
         && sender.send(orbit_camera.into()).is_ok()
     {
+        render_control.render_source.view = Some(orbit_camera.clone());
         render_control.camera_updated = false;
+        render_controls::restarted(render_control, ui.input(|i| i.time));
     }
 
     if render_control.render_requested {
@@ -252,9 +460,10 @@ pub fn render_output(
         render_control.render_receiver = None;
         render_control.camera_config_sender = None;
 
-        if !render_control.camera_updated {
+        if !render_control.camera_updated && !render_control.scene_updated {
             render_control.scene = None;
             render_control.orbit_camera = None;
+            render_control.fly_camera = None;
         }
     }
 
@@ -264,7 +473,12 @@ pub fn render_output(
         && let Some(resources) = rendered_image.render_resources.as_ref()
     {
         if render_control.scene.is_none()
-            && let Ok(s) = parse_scene_yaml(scene_yaml, 0)
+            && let Ok(s) = parse_scene_yaml_with_overrides(
+                scene_yaml,
+                &interactive_template_context(viewport_size),
+                &render_control.scene_overrides(),
+                render_control.variant.as_deref(),
+            )
         {
             let ctx = CreatorContext {
                 screen_width: viewport_size.x as usize,
@@ -273,8 +487,29 @@ pub fn render_output(
                 queue: &resources.queue,
             };
 
-            render_control.orbit_camera = Some(OrbitCamera::new(&s.camera, &ctx, 1.));
-            render_control.scene = Some(s);
+            match OrbitCamera::new(
+                &s.camera,
+                &ctx,
+                render_control.camera_settings.damping_factor,
+            ) {
+                Ok(orbit_camera) => {
+                    let mut orbit_camera =
+                        render_control.restored_view.take().unwrap_or(orbit_camera);
+                    if let Ok(bounds) = s.bounding_box() {
+                        orbit_camera.set_scene_bounds(&bounds);
+                    }
+                    render_control.orbit_camera = Some(orbit_camera);
+                    render_control.fly_camera = None;
+                    render_control.scene = Some(s);
+                    render_control.render_source = RenderSource {
+                        scene_yaml: scene_yaml.to_owned(),
+                        variant: render_control.variant.clone(),
+                        camera: render_control.camera.clone(),
+                        view: None,
+                    };
+                }
+                Err(err) => error_info.handle(err),
+            }
         }
 
         if let (Some(scene), Some(orbit_camera)) =
@@ -282,31 +517,305 @@ pub fn render_output(
         {
             scene.camera.look_from = orbit_camera.look_from().into();
             scene.camera.look_at = Some(orbit_camera.look_at().into());
+            scene.camera.up = Some(orbit_camera.up.into());
+            scene.camera.roll_degrees = Some(orbit_camera.roll_degrees());
+            scene.camera.focus_distance = None;
         }
 
+        let render_size = if render_control.reduced_resolution {
+            (viewport_size * render_control.camera_settings.interaction_resolution as f32)
+                .round()
+                .max(Vec2::splat(1.))
+        } else {
+            viewport_size
+        };
+        render_control.render_source.view = render_control.orbit_camera.clone();
+
+        // A region is rendered with a camera and size of its own, so the scene is kept as is
+        let mut scene = render_control.scene.clone();
+        if let Err(err) = render_control.render_region.start(
+            &mut scene,
+            render_control.orbit_camera.as_ref(),
+            rendered_image,
+        ) {
+            error_info.handle(err);
+        }
         let res = render(
             scene_yaml,
-            render_control.scene.clone(),
-            viewport_size,
+            scene,
+            render_control.scene_overrides(),
+            render_control.variant.clone(),
+            render_size,
             ui.ctx(),
             resources.clone(),
         );
-        rendered_image.width = viewport_size.x as u32;
-        rendered_image.height = viewport_size.y as u32;
+        render_control.render_size = render_size;
         render_control.render_receiver = Some(res.0);
         render_control.abort_sender = Some(res.1);
         render_control.camera_config_sender = Some(res.2);
         render_control.render_requested = false;
-        if !render_control.camera_updated {
+        render_controls::started(render_control, ui.input(|i| i.time));
+        if !render_control.camera_updated && !render_control.scene_updated {
             render_control.loading_scene = true;
         }
         render_control.camera_updated = false;
+        render_control.scene_updated = false;
+    }
+}
+
+/// Restarts the render at the interaction resolution when the camera starts moving, and at
+/// full resolution when it has settled. The scene model is kept, so only the renderer restarts
+fn change_resolution_for_interaction(
+    ui: &Ui,
+    render_control: &mut RenderControl,
+    camera_moved: bool,
+) {
+    if render_control.scene.is_none() || render_control.render_receiver.is_none() {
+        return;
+    }
+
+    if camera_moved
+        && !render_control.reduced_resolution
+        && render_control.camera_settings.interaction_resolution < 1.
+    {
+        render_control.reduced_resolution = true;
+        render_control.scene_updated = true;
+        render_control.render_requested = true;
+    } else if render_control.reduced_resolution && !camera_moved {
+        let settled_in = render_control.last_camera_move + SETTLE_SECONDS - ui.input(|i| i.time);
+        if settled_in <= 0. {
+            render_control.reduced_resolution = false;
+            render_control.scene_updated = true;
+            render_control.render_requested = true;
+        } else {
+            ui.ctx()
+                .request_repaint_after(Duration::from_secs_f64(settled_in));
+        }
+    }
+}
+
+/// Focuses the camera on the object at the given point of the image, where 0, 0 is the top
+/// left and 1, 1 the bottom right corner
+fn focus_at(
+    render_control: &mut RenderControl,
+    x: f64,
+    y: f64,
+    aspect_ratio: f64,
+) -> Result<(), Box<dyn Error>> {
+    let (Some(scene), Some(orbit_camera)) =
+        (&render_control.scene, &mut render_control.orbit_camera)
+    else {
+        return Ok(());
+    };
+
+    let look_from = orbit_camera.look_from();
+    let direction = orbit_camera.ray_direction(x, y, aspect_ratio);
+    let Some(hit) = scene.ray_cast(look_from, direction)? else {
+        return Ok(());
+    };
+
+    // The focus plane is perpendicular to the view direction, so off center hits are projected
+    let forward = (orbit_camera.look_at() - look_from).unit();
+    let focus_distance = hit.distance * direction.dot(forward);
+
+    orbit_camera.set_focus_distance(focus_distance);
+    if let Some(fly_camera) = &mut render_control.fly_camera {
+        fly_camera.focus_distance = focus_distance;
+    }
+    render_control.camera_updated = true;
+    Ok(())
+}
+
+/// Selects the object at the given point of the image, and moves the editor cursor to it
+fn pick_at(
+    render_control: &mut RenderControl,
+    scene_yaml: &str,
+    x: f64,
+    y: f64,
+    aspect_ratio: f64,
+    ctx: &Context,
+) -> Result<(), Box<dyn Error>> {
+    let (Some(scene), Some(orbit_camera)) = (&render_control.scene, &render_control.orbit_camera)
+    else {
+        return Ok(());
+    };
+
+    let hit = scene.ray_cast(
+        orbit_camera.look_from(),
+        orbit_camera.ray_direction(x, y, aspect_ratio),
+    )?;
+    render_control.selected_object = hit.map(|h| h.index);
+
+    if let Some(hit) = hit {
+        let line = list_item_line(scene_yaml, "world", hit.index, scene.world.len())
+            .filter(|_| !render_control.variant_replaces("world"))
+            .ok_or(
+            "The picked object was not found in the scene yaml, it may be generated by a template or a variant",
+        )?;
+        move_cursor_to_line(scene_yaml, line, ctx);
+    }
+    Ok(())
+}
+
+/// Draws the bounding box of the selected object over the rendered image
+fn highlight_selected_object(
+    ui: &Ui,
+    render_control: &RenderControl,
+    rect: Rect,
+) -> Result<(), Box<dyn Error>> {
+    let (Some(scene), Some(orbit_camera), Some(index)) = (
+        &render_control.scene,
+        &render_control.orbit_camera,
+        render_control.selected_object,
+    ) else {
+        return Ok(());
+    };
+    let Some(hittable) = scene.world.get(index) else {
+        return Ok(());
+    };
+
+    let aabb = hittable.bounding_box()?;
+    let corners = box_corners(
+        Vec3::new(aabb.x.min, aabb.y.min, aabb.z.min),
+        Vec3::new(aabb.x.max, aabb.y.max, aabb.z.max),
+        &NopTransformer(),
+    )
+    .map(|c| {
+        orbit_camera
+            .project(c, rect.aspect_ratio() as f64)
+            .map(|(x, y)| rect.lerp_inside(Vec2::new(x as f32, y as f32)))
+    });
+
+    // Corners differing in a single axis are connected by an edge
+    let stroke = Stroke::new(2., ui.visuals().selection.bg_fill);
+    let painter = ui.painter_at(rect);
+    for a in 0..corners.len() {
+        for axis in [1, 2, 4] {
+            let b = a | axis;
+            if a != b
+                && let (Some(pa), Some(pb)) = (corners[a], corners[b])
+            {
+                painter.line_segment([pa, pb], stroke);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Arrow keys orbit, with shift they pan, [ and ] roll, plus and minus zoom, and 1, 3 and 7
+/// snap to the front, right and top views, or with ctrl to the opposite ones. Keys are ignored
+/// while another widget, like the editor, has focus
+fn orbit_camera_keys(ui: &Ui, orbit_camera: &mut OrbitCamera, settings: &CameraSettings) -> bool {
+    if ui.ctx().memory(|m| m.focused().is_some()) {
+        return false;
+    }
+
+    let (horizontal, vertical, roll, zoom, view, shift) = ui.input(|i| {
+        let axis = |positive: Key, negative: Key| {
+            i.key_pressed(positive) as i32 as f64 - i.key_pressed(negative) as i32 as f64
+        };
+        let opposite = i.modifiers.command;
+        let view = if i.key_pressed(Key::Num1) {
+            Some(if opposite { View::Back } else { View::Front })
+        } else if i.key_pressed(Key::Num3) {
+            Some(if opposite { View::Left } else { View::Right })
+        } else if i.key_pressed(Key::Num7) {
+            Some(if opposite { View::Bottom } else { View::Top })
+        } else {
+            None
+        };
+        (
+            axis(Key::ArrowRight, Key::ArrowLeft),
+            axis(Key::ArrowDown, Key::ArrowUp),
+            axis(Key::CloseBracket, Key::OpenBracket),
+            i.key_pressed(Key::Minus) as i32 as f64
+                - (i.key_pressed(Key::Plus) || i.key_pressed(Key::Equals)) as i32 as f64,
+            view,
+            i.modifiers.shift,
+        )
+    });
+
+    let step = settings.key_step_degrees.to_radians();
+    if horizontal != 0. || vertical != 0. {
+        if shift {
+            // A tenth of the scene size for each press
+            orbit_camera.pan(-horizontal * 0.1, vertical * 0.1);
+        } else {
+            orbit_camera.orbit(-horizontal * step, -vertical * step);
+        }
+    }
+    if roll != 0. {
+        orbit_camera.roll(roll * step);
+    }
+    if zoom != 0. {
+        // A tenth of the current distance, relative to the scene size zooming is measured in
+        orbit_camera.zoom(zoom * 0.1 * orbit_camera.target_distance / orbit_camera.scene_size);
+    }
+    if let Some(view) = view {
+        orbit_camera.snap_to(view);
+    }
+
+    horizontal != 0. || vertical != 0. || roll != 0. || zoom != 0. || view.is_some()
+}
+
+/// WASD moves, Q and E move up and down, dragging looks around, shift speeds up and scrolling
+/// changes the speed. Keys are ignored while another widget, like the editor, has focus
+fn fly_camera_input(ui: &Ui, response: &Response, fly_camera: &mut FlyCamera) -> bool {
+    let mut input_changed = false;
+
+    if response.dragged_by(PointerButton::Primary) || response.dragged_by(PointerButton::Secondary)
+    {
+        let delta = response.drag_delta();
+        if delta.x != 0.0 || delta.y != 0.0 {
+            fly_camera.look_around(-delta.x as f64 * 0.005, -delta.y as f64 * 0.005);
+            input_changed = true;
+        }
+    }
+
+    let scroll = ui.input(|i| i.smooth_scroll_delta.y);
+    if scroll != 0.0 {
+        fly_camera.change_speed((scroll as f64 * 0.005).exp());
+    }
+
+    if ui.ctx().memory(|m| m.focused().is_some()) {
+        return input_changed;
+    }
+
+    let (forward, right, up, fast, dt) = ui.input(|i| {
+        let axis = |positive: Key, negative: Key| {
+            i.key_down(positive) as i32 as f64 - i.key_down(negative) as i32 as f64
+        };
+        (
+            axis(Key::W, Key::S),
+            axis(Key::D, Key::A),
+            axis(Key::Q, Key::E),
+            i.modifiers.shift,
+            i.stable_dt as f64,
+        )
+    });
+    if forward != 0. || right != 0. || up != 0. {
+        let distance = fly_camera.speed * dt * if fast { 4. } else { 1. };
+        fly_camera.fly(forward * distance, right * distance, up * distance);
+        input_changed = true;
+    }
+
+    input_changed
+}
+
+fn interactive_template_context(viewport_size: Vec2) -> TemplateContext {
+    TemplateContext {
+        width: viewport_size.x as usize,
+        height: viewport_size.y as usize,
+        mode: RenderMode::Interactive,
+        ..Default::default()
     }
 }
 
 fn render(
     scene_yaml: &str,
     scene: Option<Scene>,
+    overrides: Vec<SceneOverride>,
+    variant: Option<String>,
     viewport_size: Vec2,
     ctx: &Context,
     resources: Arc<RenderResources>,
@@ -333,7 +842,12 @@ fn render(
         let res = (|| {
             let scene = match scene {
                 Some(s) => s,
-                None => parse_scene_yaml(&scene_yaml_str, 0)?,
+                None => parse_scene_yaml_with_overrides(
+                    &scene_yaml_str,
+                    &interactive_template_context(viewport_size),
+                    &overrides,
+                    variant.as_deref(),
+                )?,
             }
             .create(&CreatorContext {
                 screen_width: viewport_size.x as usize,
@@ -341,6 +855,13 @@ fn render(
                 device: &resources.device,
                 queue: &resources.queue,
             })?;
+            render_sender_clone
+                .send(RenderMessage::Started(
+                    scene.render_config.width,
+                    scene.render_config.height,
+                    scene.render_config.samples_per_pixel,
+                ))
+                .unwrap_or(());
 
             ray_trace(
                 scene,
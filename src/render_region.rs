@@ -0,0 +1,574 @@
+use std::error::Error;
+use std::sync::Arc;
+
+use eframe::egui::{Pos2, Rect, Response, Stroke, StrokeKind, Ui, Vec2};
+use eframe::wgpu;
+use eframe::wgpu::util::DeviceExt;
+use solstrale::camera::CameraConfig;
+use solstrale::geo::vec3::Vec3;
+
+use crate::model::custom_width_height::CustomWidthHeight;
+use crate::model::orbit_camera::OrbitCamera;
+use crate::model::scene::Scene;
+use crate::model::width_height::WidthHeight;
+use crate::{RenderResources, RenderedImage};
+
+/// Largest width and height of the region render, the same as for a custom `width_height`
+const MAX_SIZE: u32 = 8000;
+
+/// Copies the rendered region into the full image. Every pixel of the region in the full
+/// image is mapped to the region render by the homography and sampled bilinearly
+const COMPOSITE_SHADER: &str = r#"
+struct Composite {
+    homography: mat3x3<f32>,
+    region_min: vec2<u32>,
+    region_max: vec2<u32>,
+    width: u32,
+    region_width: u32,
+    region_height: u32,
+}
+
+@group(0) @binding(0) var<uniform> composite: Composite;
+@group(0) @binding(1) var<storage, read> region: array<vec4<f32>>;
+@group(0) @binding(2) var<storage, read_write> output: array<vec4<f32>>;
+
+fn region_pixel(x: i32, y: i32) -> vec4<f32> {
+    let cx = clamp(x, 0, i32(composite.region_width) - 1);
+    let cy = clamp(y, 0, i32(composite.region_height) - 1);
+    return region[u32(cy) * composite.region_width + u32(cx)];
+}
+
+@compute @workgroup_size(8, 8)
+fn composite_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let x = composite.region_min.x + id.x;
+    let y = composite.region_min.y + id.y;
+    if (x >= composite.region_max.x || y >= composite.region_max.y) {
+        return;
+    }
+
+    let p = composite.homography * vec3<f32>(f32(x), f32(y), 1.0);
+    let q = p.xy / p.z;
+    let i = vec2<i32>(floor(q));
+    let t = q - floor(q);
+    let top = mix(region_pixel(i.x, i.y), region_pixel(i.x + 1, i.y), t.x);
+    let bottom = mix(region_pixel(i.x, i.y + 1), region_pixel(i.x + 1, i.y + 1), t.x);
+    output[y * composite.width + x] = mix(top, bottom, t.y);
+}
+"#;
+
+/// Layout of the `Composite` uniform of the composite shader
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct CompositeUniform {
+    homography: [[f32; 4]; 3],
+    region_min: [u32; 2],
+    region_max: [u32; 2],
+    width: u32,
+    region_width: u32,
+    region_height: u32,
+    _padding: u32,
+}
+
+pub fn create_composite_pipeline(
+    device: &wgpu::Device,
+) -> (wgpu::ComputePipeline, wgpu::BindGroupLayout) {
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Composite Shader"),
+        source: wgpu::ShaderSource::Wgsl(COMPOSITE_SHADER.into()),
+    });
+
+    let storage = |binding, read_only| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    };
+    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Composite Bind Group Layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            storage(1, true),
+            storage(2, false),
+        ],
+    });
+
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Composite Pipeline Layout"),
+        bind_group_layouts: &[&bind_group_layout],
+        push_constant_ranges: &[],
+    });
+
+    let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some("Composite Pipeline"),
+        layout: Some(&pipeline_layout),
+        module: &shader,
+        entry_point: Some("composite_main"),
+        compilation_options: Default::default(),
+        cache: None,
+    });
+
+    (pipeline, bind_group_layout)
+}
+
+/// Camera and size the region is rendered with, and the homography from the pixels of the
+/// full image to the pixels of the region render
+struct RegionCamera {
+    config: CameraConfig,
+    width: u32,
+    height: u32,
+    homography: [Vec3; 3],
+}
+
+/// Columns of the matrix from the pixel coordinates (x, y, 1) of an image to the directions
+/// of the rays the renderer shoots through the pixels, which it does from their centers at
+/// `(x + 0.5) / (width - 1)`
+fn pixel_to_direction(config: &CameraConfig, width: u32, height: u32) -> [Vec3; 3] {
+    let h = (config.vertical_fov_degrees.to_radians() / 2.).tan();
+    let w = (config.look_from - config.look_at).unit();
+    let u = config.up.unit().cross(w).unit();
+    let v = w.cross(u);
+
+    let horizontal = u * (2. * h * width as f64 / height as f64);
+    let vertical = v * (2. * h);
+    let sx = 1. / (width - 1) as f64;
+    let sy = 1. / (height - 1) as f64;
+    [
+        horizontal * sx,
+        vertical * -sy,
+        horizontal * (0.5 * sx - 0.5) + vertical * (0.5 - 0.5 * sy) - w,
+    ]
+}
+
+/// Replaces the camera of the scene with the region camera. Its up vector is already rolled
+fn set_scene_camera(
+    scene_camera: &mut crate::model::camera_config::CameraConfig,
+    camera: &RegionCamera,
+) {
+    scene_camera.vertical_fov_degrees = Some(camera.config.vertical_fov_degrees);
+    scene_camera.aperture_size = Some(camera.config.aperture_size);
+    scene_camera.focus_distance = None;
+    scene_camera.lens = None;
+    scene_camera.path = None;
+    scene_camera.look_from = camera.config.look_from.into();
+    scene_camera.look_at = Some(camera.config.look_at.into());
+    scene_camera.up = Some(camera.config.up.into());
+    scene_camera.roll_degrees = None;
+}
+
+/// Solves `m * x = d`, for the matrix with the given columns
+fn solve(m: &[Vec3; 3], d: Vec3) -> Vec3 {
+    let [a, b, c] = *m;
+    let det = a.dot(b.cross(c));
+    Vec3::new(
+        b.cross(c).dot(d) / det,
+        c.cross(a).dot(d) / det,
+        a.cross(b).dot(d) / det,
+    )
+}
+
+/// A camera can not render an off-axis part of its view, so the region is rendered by a
+/// camera at the same position turned towards the center of the region, with the smallest
+/// field of view that covers it. Rays from one position through the same pixels hit the same
+/// points, so this is exact up to the sampling, except for the tilt of the plane in focus
+/// with depth of field
+fn region_camera(
+    full: &CameraConfig,
+    width: u32,
+    height: u32,
+    min: [u32; 2],
+    max: [u32; 2],
+) -> RegionCamera {
+    let m = pixel_to_direction(full, width, height);
+    let direction = |x: f64, y: f64| m[0] * x + m[1] * y + m[2];
+
+    let w = (full.look_from - full.look_at).unit();
+    let focus_distance = (full.look_from - full.look_at).length();
+    let center = direction(
+        (min[0] + max[0] - 1) as f64 / 2.,
+        (min[1] + max[1] - 1) as f64 / 2.,
+    )
+    .unit();
+    // Keeps the focus point on the plane in focus of the full image
+    let look_at = full.look_from + center * (focus_distance / center.dot(w.neg()));
+
+    // Point in the image plane of the region camera that a pixel of the full image is seen at
+    let region_w = center.neg();
+    let region_u = full.up.unit().cross(region_w).unit();
+    let region_v = region_w.cross(region_u);
+    let project = |x: f64, y: f64| {
+        let d = direction(x, y);
+        let z = -d.dot(region_w);
+        (d.dot(region_u) / z, d.dot(region_v) / z)
+    };
+
+    // Extent of the corners of the region, with a pixel of margin for the sampling, and the
+    // smallest distance between neighboring pixels. Pixels away from the center of the full
+    // image are closer together in the turned image plane
+    let (mut half_width, mut half_height) = (0f64, 0f64);
+    let mut pixel_distance = f64::MAX;
+    for x in [min[0] as f64 - 1., max[0] as f64] {
+        for y in [min[1] as f64 - 1., max[1] as f64] {
+            let (px, py) = project(x, y);
+            half_width = half_width.max(px.abs());
+            half_height = half_height.max(py.abs());
+
+            let (nx, _) = project(x + 1., y);
+            let (_, ny) = project(x, y + 1.);
+            pixel_distance = pixel_distance.min((nx - px).abs()).min((ny - py).abs());
+        }
+    }
+
+    // Renders with pixels no larger than those of the full image
+    let region_height = ((2. * half_height / pixel_distance).ceil() as u32 + 1).clamp(2, MAX_SIZE);
+    let region_width =
+        ((region_height as f64 * half_width / half_height).ceil() as u32).clamp(2, MAX_SIZE);
+
+    let config = CameraConfig {
+        vertical_fov_degrees: (2. * half_height.atan()).to_degrees(),
+        aperture_size: full.aperture_size,
+        look_from: full.look_from,
+        look_at,
+        up: full.up,
+    };
+    let region_m = pixel_to_direction(&config, region_width, region_height);
+    RegionCamera {
+        config,
+        width: region_width,
+        height: region_height,
+        homography: m.map(|column| solve(&region_m, column)),
+    }
+}
+
+/// A region being rendered and composited over the image it was drawn on
+struct ActiveRegion {
+    /// Region in pixels of the full image, from `min` up to `max`
+    min: [u32; 2],
+    max: [u32; 2],
+    /// Uv rect of the region, for outlining it
+    rect: Rect,
+    /// The full image with the region composited over it
+    output: wgpu::Buffer,
+    uniform: wgpu::Buffer,
+}
+
+/// Drawing a region in the viewport, and rendering only that region over the last image
+#[derive(Default)]
+pub struct RenderRegion {
+    /// Dragging in the viewport draws a region instead of controlling the camera
+    pub selecting: bool,
+    /// Where the drag started, from 0, 0 at the top left to 1, 1 at the bottom right of the
+    /// image
+    drag_start: Option<Pos2>,
+    /// Region to render, in the same coordinates
+    region: Option<Rect>,
+    /// The next render is of the region
+    requested: bool,
+    active: Option<ActiveRegion>,
+}
+
+impl RenderRegion {
+    pub fn is_active(&self) -> bool {
+        self.active.is_some()
+    }
+
+    /// Renders the active region again when resuming, instead of the full image
+    pub fn request_resume(&mut self) {
+        self.requested = self.active.is_some();
+    }
+
+    /// Draws the region by dragging over the image, which is painted in `rect`. Returns true
+    /// when a region is drawn and should be rendered
+    pub fn select(&mut self, ui: &Ui, response: &Response, rect: Rect, viewport: Rect) -> bool {
+        let to_uv = |pos: Pos2| {
+            ((pos - rect.min) / rect.size())
+                .clamp(Vec2::ZERO, Vec2::splat(1.))
+                .to_pos2()
+        };
+        let to_screen = |uv: Rect| {
+            Rect::from_min_max(
+                rect.min + uv.min.to_vec2() * rect.size(),
+                rect.min + uv.max.to_vec2() * rect.size(),
+            )
+        };
+        let painter = ui.painter_at(viewport);
+        let stroke = Stroke::new(1.5, ui.visuals().selection.stroke.color);
+
+        if response.drag_started()
+            && let Some(pos) = response.interact_pointer_pos()
+        {
+            self.drag_start = Some(to_uv(pos));
+        }
+        if let (Some(start), Some(pos)) = (self.drag_start, response.interact_pointer_pos()) {
+            let uv = Rect::from_two_pos(start, to_uv(pos));
+            painter.rect_stroke(to_screen(uv), 0., stroke, StrokeKind::Middle);
+            if response.drag_stopped() {
+                self.drag_start = None;
+                // Ignores clicks and slips of the mouse
+                let size = to_screen(uv).size();
+                if size.x >= 2. && size.y >= 2. {
+                    self.region = Some(uv);
+                    self.requested = true;
+                    return true;
+                }
+            }
+        } else if let Some(active) = &self.active {
+            painter.rect_stroke(to_screen(active.rect), 0., stroke, StrokeKind::Middle);
+        }
+        false
+    }
+
+    /// Prepares the scene for the next render. When a region is requested, the camera of the
+    /// scene is turned to it and the size set to the region, and the last image is kept to
+    /// composite the region over. Otherwise the full image is rendered
+    pub fn start(
+        &mut self,
+        scene: &mut Option<Scene>,
+        orbit_camera: Option<&OrbitCamera>,
+        rendered_image: &RenderedImage,
+    ) -> Result<(), Box<dyn Error>> {
+        self.active = None;
+        if !std::mem::take(&mut self.requested) {
+            return Ok(());
+        }
+        let (Some(scene), Some(orbit_camera), Some(resources), Some(base), Some(region)) = (
+            scene,
+            orbit_camera,
+            &rendered_image.render_resources,
+            &rendered_image.output_buffer,
+            self.region,
+        ) else {
+            return Err("Render the full image before rendering a region".into());
+        };
+
+        let (width, height) = (rendered_image.width, rendered_image.height);
+        if width < 2 || height < 2 {
+            return Err("The image is too small to render a region of".into());
+        }
+        let min = [
+            (region.min.x * width as f32).floor() as u32,
+            (region.min.y * height as f32).floor() as u32,
+        ];
+        let max = [
+            ((region.max.x * width as f32).ceil() as u32).clamp(min[0] + 1, width),
+            ((region.max.y * height as f32).ceil() as u32).clamp(min[1] + 1, height),
+        ];
+        let camera = region_camera(&orbit_camera.into(), width, height, min, max);
+
+        set_scene_camera(&mut scene.camera, &camera);
+        scene
+            .render_configuration
+            .get_or_insert_default()
+            .width_height = Some(WidthHeight {
+            screen: None,
+            half_screen: None,
+            quarter_screen: None,
+            custom: Some(CustomWidthHeight {
+                width: camera.width as usize,
+                height: camera.height as usize,
+            }),
+        });
+
+        let device = &resources.device;
+        let output = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Region Output Buffer"),
+            size: base.size(),
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.copy_buffer_to_buffer(base, 0, &output, 0, base.size());
+        resources.queue.submit(Some(encoder.finish()));
+
+        let uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Composite Buffer"),
+            contents: bytemuck::bytes_of(&CompositeUniform {
+                homography: camera
+                    .homography
+                    .map(|c| [c.x as f32, c.y as f32, c.z as f32, 0.]),
+                region_min: min,
+                region_max: max,
+                width,
+                region_width: camera.width,
+                region_height: camera.height,
+                _padding: 0,
+            }),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        self.active = Some(ActiveRegion {
+            min,
+            max,
+            rect: region,
+            output,
+            uniform,
+        });
+        Ok(())
+    }
+
+    /// Composites a sample of the region render over the image. Returns the full image to show,
+    /// or `None` when no region is rendered
+    pub fn composite(
+        &self,
+        resources: Option<&RenderResources>,
+        region_buffer: &wgpu::Buffer,
+    ) -> Option<Arc<wgpu::Buffer>> {
+        let (Some(active), Some(resources)) = (&self.active, resources) else {
+            return None;
+        };
+
+        let bind_group = resources
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: None,
+                layout: &resources.composite_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: active.uniform.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: region_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: active.output.as_entire_binding(),
+                    },
+                ],
+            });
+
+        let mut encoder = resources
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Composite Pass"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&resources.composite_pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(
+                (active.max[0] - active.min[0]).div_ceil(8),
+                (active.max[1] - active.min[1]).div_ceil(8),
+                1,
+            );
+        }
+        resources.queue.submit(Some(encoder.finish()));
+
+        // A new Arc for every sample, so that views of the image see that it changed
+        Some(Arc::new(active.output.clone()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::orbit_camera::rolled_up;
+
+    fn full_camera() -> CameraConfig {
+        CameraConfig {
+            vertical_fov_degrees: 60.,
+            aperture_size: 0.,
+            look_from: Vec3::new(1., 2., 10.),
+            look_at: Vec3::new(0., 0., 0.),
+            up: Vec3::new(0., 1., 0.),
+        }
+    }
+
+    fn apply(homography: &[Vec3; 3], x: f64, y: f64) -> (f64, f64) {
+        let p = homography[0] * x + homography[1] * y + homography[2];
+        (p.x / p.z, p.y / p.z)
+    }
+
+    #[test]
+    fn whole_image() {
+        let camera = region_camera(&full_camera(), 200, 100, [0, 0], [200, 100]);
+
+        // Looks about the same way, since the renderer shoots rays half a pixel off center
+        let look = |c: &CameraConfig| (c.look_at - c.look_from).unit();
+        assert!((look(&camera.config) - look(&full_camera())).length() < 0.01);
+        assert!((200..=215).contains(&camera.width), "{}", camera.width);
+        assert!((100..=108).contains(&camera.height), "{}", camera.height);
+
+        // Every pixel maps to about the same pixel, with the margin
+        let (x, y) = apply(&camera.homography, 0., 0.);
+        assert!(
+            (0.5..=2.5).contains(&x) && (0.5..=2.5).contains(&y),
+            "{x}, {y}"
+        );
+    }
+
+    #[test]
+    fn off_axis_region() {
+        let full = full_camera();
+        let camera = region_camera(&full, 200, 100, [150, 10], [190, 40]);
+
+        // Pixels of the region are seen in the same direction by both cameras
+        let m = pixel_to_direction(&full, 200, 100);
+        let region_m = pixel_to_direction(&camera.config, camera.width, camera.height);
+        for (px, py) in [(150., 10.), (189., 39.), (170., 25.)] {
+            let (x, y) = apply(&camera.homography, px, py);
+            assert!(x >= 0. && y >= 0., "{x}, {y}");
+            assert!(x <= (camera.width - 1) as f64 && y <= (camera.height - 1) as f64);
+
+            let expected = (m[0] * px + m[1] * py + m[2]).unit();
+            let actual = (region_m[0] * x + region_m[1] * y + region_m[2]).unit();
+            assert!((expected - actual).length() < 1e-9);
+        }
+
+        // Neighboring pixels of the full image are at least a pixel apart in the region
+        for (px, py) in [(150., 10.), (189., 39.), (150., 39.), (189., 10.)] {
+            let (x, y) = apply(&camera.homography, px, py);
+            let (nx, _) = apply(&camera.homography, px + 1., py);
+            let (_, ny) = apply(&camera.homography, px, py + 1.);
+            assert!(nx - x >= 0.99 && ny - y >= 0.99, "{}, {}", nx - x, ny - y);
+        }
+        assert!(camera.width < 100 && camera.height < 100);
+    }
+
+    #[test]
+    fn rolled_camera() {
+        let full = CameraConfig {
+            up: rolled_up(
+                Vec3::new(0., 1., 0.),
+                Vec3::new(1., 2., 10.),
+                Vec3::new(0., 0., 0.),
+                0.4,
+            ),
+            ..full_camera()
+        };
+        let camera = region_camera(&full, 200, 100, [20, 60], [80, 95]);
+
+        let m = pixel_to_direction(&full, 200, 100);
+        let region_m = pixel_to_direction(&camera.config, camera.width, camera.height);
+        for (px, py) in [(20., 60.), (79., 94.), (50., 80.)] {
+            let (x, y) = apply(&camera.homography, px, py);
+            let expected = (m[0] * px + m[1] * py + m[2]).unit();
+            let actual = (region_m[0] * x + region_m[1] * y + region_m[2]).unit();
+            assert!((expected - actual).length() < 1e-9);
+        }
+
+        // The roll of the interactive camera is not applied again to the region camera
+        let mut scene_camera: crate::model::camera_config::CameraConfig =
+            serde_yaml::from_str("look_from: 0, 0, 1\nroll_degrees: 20").unwrap();
+        set_scene_camera(&mut scene_camera, &camera);
+        assert_eq!(None, scene_camera.roll_degrees);
+        assert_eq!(Some(camera.config.up.into()), scene_camera.up);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::display_settings::buffer_to_display_image;
 use crate::{ErrorInfo, RenderedImage};
 use eframe::egui;
 use egui::Context;
@@ -26,13 +27,23 @@ pub fn handle_dialog(
             .expect("Dialog is only displayed when there is an image")
             .as_ref();
 
-        let image = buffer_to_image(
-            &render_resources.device,
-            &render_resources.queue,
-            image_buffer,
-            rendered_image.width,
-            rendered_image.height,
-        );
+        let image = if rendered_image.display_settings.apply_to_saved_image {
+            buffer_to_display_image(
+                render_resources,
+                image_buffer,
+                rendered_image.width,
+                rendered_image.height,
+                &rendered_image.display_settings,
+            )
+        } else {
+            buffer_to_image(
+                &render_resources.device,
+                &render_resources.queue,
+                image_buffer,
+                rendered_image.width,
+                rendered_image.height,
+            )
+        };
 
         if let Err(err) = image::save_buffer(
             file_path,
@@ -0,0 +1,490 @@
+use std::error::Error;
+use std::sync::mpsc::{Receiver, TryRecvError, channel};
+use std::thread;
+
+use eframe::egui::{
+    Button, Color32, ColorImage, Context, CursorIcon, FontId, Id, Rect, ScrollArea, Sense, Stroke,
+    TextureHandle, TextureOptions, Ui, Vec2, pos2,
+};
+
+use crate::display_settings::DisplaySettings;
+use crate::image_view::{ImageView, read_pixels};
+use crate::model::orbit_camera::OrbitCamera;
+use crate::{ErrorInfo, RenderControl, RenderedImage};
+
+/// Height of the thumbnails in the history strip
+const THUMBNAIL_HEIGHT: f32 = 64.;
+
+/// What the image being rendered is rendered from, recorded when the render starts since the
+/// scene yaml may have been edited since
+#[derive(Clone, Default)]
+pub struct RenderSource {
+    pub scene_yaml: String,
+    pub variant: Option<String>,
+    /// Selected camera bookmark
+    pub camera: Option<String>,
+    /// View of the interactive camera, which the scene camera is replaced with
+    pub view: Option<OrbitCamera>,
+}
+
+/// A rendered image kept for comparing, with the scene that produced it
+pub struct Snapshot {
+    id: usize,
+    pub name: String,
+    pub source: RenderSource,
+    pub width: u32,
+    pub height: u32,
+    pub progress: f64,
+    pub fps: f64,
+    /// Raw rendered values, row by row from the top left
+    pixels: Vec<[f32; 4]>,
+    /// Texture of the pixels, with the display settings it was made with
+    texture: Option<(DisplaySettings, TextureHandle)>,
+}
+
+impl Snapshot {
+    fn size(&self) -> Vec2 {
+        Vec2::new(self.width as f32, self.height as f32)
+    }
+
+    /// Texture of the snapshot as it is shown with the display settings, which is made again
+    /// when the settings change
+    fn texture(&mut self, ui: &Ui, settings: &DisplaySettings) -> &TextureHandle {
+        if self.texture.as_ref().is_none_or(|(s, _)| s != settings) {
+            let texture = ui.ctx().load_texture(
+                format!("snapshot-{}", self.id),
+                color_image(self.width, self.height, &self.pixels, settings),
+                TextureOptions::NEAREST,
+            );
+            self.texture = Some((*settings, texture));
+        }
+        &self.texture.as_ref().expect("Texture is created above").1
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub enum CompareMode {
+    /// Snapshot A on the left and B on the right of a movable split
+    #[default]
+    Wipe,
+    /// Absolute difference between the snapshots
+    Difference,
+}
+
+/// History of snapshots, and the two of them compared in the viewport
+pub struct Snapshots {
+    snapshots: Vec<Snapshot>,
+    next_id: usize,
+    /// Ids of the snapshots compared
+    a: Option<usize>,
+    b: Option<usize>,
+    comparing: bool,
+    pub mode: CompareMode,
+    /// Position of the wipe, as a fraction of the image width
+    split: f32,
+    /// Difference of the compared snapshots, with the ids and display settings it was made for
+    difference: Option<(usize, usize, DisplaySettings, TextureHandle)>,
+    /// Snapshot being taken, which is added when its pixels are read back from the GPU
+    taking: Option<Receiver<Snapshot>>,
+}
+
+impl Default for Snapshots {
+    fn default() -> Self {
+        Snapshots {
+            snapshots: Vec::new(),
+            next_id: 1,
+            a: None,
+            b: None,
+            comparing: false,
+            mode: CompareMode::default(),
+            split: 0.5,
+            difference: None,
+            taking: None,
+        }
+    }
+}
+
+impl Snapshots {
+    /// Keeps the rendered image with what it was rendered from. The pixels are read back on
+    /// another thread, and the snapshot is added by [Snapshots::receive] when they arrive
+    pub fn take(
+        &mut self,
+        ctx: &Context,
+        rendered_image: &RenderedImage,
+        source: &RenderSource,
+    ) -> Result<(), Box<dyn Error>> {
+        let (Some(resources), Some(buffer)) = (
+            &rendered_image.render_resources,
+            &rendered_image.output_buffer,
+        ) else {
+            return Err("There is no rendered image to take a snapshot of".into());
+        };
+
+        let id = self.next_id;
+        self.next_id += 1;
+        let mut snapshot = Snapshot {
+            id,
+            name: format!("Snapshot {id}"),
+            source: source.clone(),
+            width: rendered_image.width,
+            height: rendered_image.height,
+            progress: rendered_image.progress,
+            fps: rendered_image.fps,
+            pixels: Vec::new(),
+            texture: None,
+        };
+
+        let (sender, receiver) = channel();
+        self.taking = Some(receiver);
+        let resources = resources.clone();
+        let buffer = buffer.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let count = snapshot.width as u64 * snapshot.height as u64;
+            snapshot.pixels = read_pixels(&resources, &buffer, 0, count);
+            sender.send(snapshot).ok();
+            ctx.request_repaint();
+        });
+        Ok(())
+    }
+
+    /// Adds the snapshot being taken once its pixels are read. The two latest snapshots are
+    /// selected for comparing
+    fn receive(&mut self) {
+        let Some(receiver) = &self.taking else {
+            return;
+        };
+        match receiver.try_recv() {
+            Ok(snapshot) => {
+                self.taking = None;
+                self.add(snapshot);
+            }
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Disconnected) => self.taking = None,
+        }
+    }
+
+    fn add(&mut self, snapshot: Snapshot) {
+        let id = snapshot.id;
+        self.snapshots.push(snapshot);
+        self.a = self.b.or(self.a).or(Some(id));
+        self.b = Some(id);
+    }
+
+    fn get(&self, id: Option<usize>) -> Option<&Snapshot> {
+        self.snapshots.iter().find(|s| Some(s.id) == id)
+    }
+
+    fn remove(&mut self, id: usize) {
+        self.snapshots.retain(|s| s.id != id);
+        if self.a == Some(id) {
+            self.a = None;
+        }
+        if self.b == Some(id) {
+            self.b = None;
+        }
+    }
+
+    /// The compared snapshots are shown in the viewport instead of the rendered image
+    pub fn is_comparing(&self) -> bool {
+        self.comparing && self.get(self.a).is_some() && self.get(self.b).is_some()
+    }
+
+    /// Area of the viewport the compared snapshots are painted in
+    pub fn image_rect(
+        &self,
+        viewport: Rect,
+        image_view: &ImageView,
+        pixels_per_point: f32,
+    ) -> Rect {
+        let size = self.get(self.a).map_or(Vec2::ZERO, Snapshot::size);
+        image_view.image_rect(viewport, size, pixels_per_point)
+    }
+
+    /// Paints the compared snapshots in `rect`, clipped to the viewport. The wipe is moved by
+    /// dragging its handle
+    pub fn show_comparison(
+        &mut self,
+        ui: &Ui,
+        rect: Rect,
+        viewport: Rect,
+        settings: &DisplaySettings,
+    ) {
+        let painter = ui.painter_at(viewport);
+        let uv = Rect::from_min_max(pos2(0., 0.), pos2(1., 1.));
+        let (Some(a), Some(b)) = (self.a, self.b) else {
+            return;
+        };
+
+        match self.mode {
+            CompareMode::Wipe => {
+                let x = rect.left() + rect.width() * self.split;
+                for (id, clip) in [
+                    (a, Rect::from_x_y_ranges(rect.left()..=x, rect.y_range())),
+                    (b, Rect::from_x_y_ranges(x..=rect.right(), rect.y_range())),
+                ] {
+                    if let Some(snapshot) = self.snapshots.iter_mut().find(|s| s.id == id) {
+                        let texture = snapshot.texture(ui, settings);
+                        painter.with_clip_rect(clip.intersect(viewport)).image(
+                            texture.id(),
+                            rect,
+                            uv,
+                            Color32::WHITE,
+                        );
+                    }
+                }
+
+                let stroke = Stroke::new(2., ui.visuals().strong_text_color());
+                painter.vline(x, rect.y_range().intersection(viewport.y_range()), stroke);
+                let handle = ui
+                    .interact(
+                        Rect::from_x_y_ranges(x - 6.0..=x + 6., viewport.y_range()),
+                        Id::new("snapshot-wipe"),
+                        Sense::drag(),
+                    )
+                    .on_hover_cursor(CursorIcon::ResizeHorizontal);
+                if handle.dragged() && rect.width() > 0. {
+                    self.split = (self.split + handle.drag_delta().x / rect.width()).clamp(0., 1.);
+                }
+
+                let names = [a, b].map(|id| self.get(Some(id)).map(|s| s.name.clone()));
+                if let [Some(a), Some(b)] = names {
+                    show_label(ui, viewport, &format!("{a}  |  {b}"));
+                }
+            }
+            CompareMode::Difference => {
+                let (Some(first), Some(second)) = (self.get(Some(a)), self.get(Some(b))) else {
+                    return;
+                };
+                if first.width != second.width || first.height != second.height {
+                    show_label(ui, viewport, "The snapshots have different resolutions");
+                    return;
+                }
+
+                let label = format!("|{} - {}|", first.name, second.name);
+                let cached = self
+                    .difference
+                    .as_ref()
+                    .is_some_and(|(da, db, s, _)| (*da, *db, s) == (a, b, settings));
+                if !cached {
+                    let pixels = difference(&first.pixels, &second.pixels);
+                    let texture = ui.ctx().load_texture(
+                        "snapshot-difference",
+                        color_image(first.width, first.height, &pixels, settings),
+                        TextureOptions::NEAREST,
+                    );
+                    self.difference = Some((a, b, *settings, texture));
+                }
+                if let Some((_, _, _, texture)) = &self.difference {
+                    painter.image(texture.id(), rect, uv, Color32::WHITE);
+                }
+                show_label(ui, viewport, &label);
+            }
+        }
+    }
+}
+
+/// Absolute difference of two images of the same size
+fn difference(a: &[[f32; 4]], b: &[[f32; 4]]) -> Vec<[f32; 4]> {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| {
+            [
+                (a[0] - b[0]).abs(),
+                (a[1] - b[1]).abs(),
+                (a[2] - b[2]).abs(),
+                1.,
+            ]
+        })
+        .collect()
+}
+
+fn color_image(
+    width: u32,
+    height: u32,
+    pixels: &[[f32; 4]],
+    settings: &DisplaySettings,
+) -> ColorImage {
+    let rgb: Vec<u8> = pixels
+        .iter()
+        .flat_map(|p| {
+            settings
+                .apply([p[0], p[1], p[2]])
+                .map(|v| (v.clamp(0., 1.) * 255.).round() as u8)
+        })
+        .collect();
+    ColorImage::from_rgb([width as usize, height as usize], &rgb)
+}
+
+fn show_label(ui: &Ui, viewport: Rect, text: &str) {
+    let painter = ui.painter_at(viewport);
+    let galley = painter.layout_no_wrap(
+        text.to_owned(),
+        FontId::proportional(13.),
+        ui.visuals().text_color(),
+    );
+    let pos = viewport.center_top() + Vec2::new(-galley.size().x / 2., 8.);
+    painter.rect_filled(
+        Rect::from_min_size(pos, galley.size()).expand(4.),
+        2.,
+        ui.visuals().extreme_bg_color.gamma_multiply(0.8),
+    );
+    painter.galley(pos, galley, ui.visuals().text_color());
+}
+
+/// Shows the history strip of snapshots, with the controls for taking, comparing, restoring
+/// and removing them
+pub fn show_strip(
+    ui: &mut Ui,
+    render_control: &mut RenderControl,
+    rendered_image: &RenderedImage,
+    scene_yaml: &mut String,
+    error_info: &mut ErrorInfo,
+) {
+    let snapshots = &mut render_control.snapshots;
+    snapshots.receive();
+    ui.horizontal(|ui| {
+        if ui
+            .add_enabled(
+                rendered_image.progress > 0. && snapshots.taking.is_none(),
+                Button::new("Take snapshot"),
+            )
+            .on_hover_text(
+                "Keep the rendered image with the scene yaml, variant and camera view that produced it",
+            )
+            .clicked()
+            && let Err(err) = snapshots.take(ui.ctx(), rendered_image, &render_control.render_source)
+        {
+            error_info.handle(err);
+        }
+        ui.separator();
+
+        ui.add_enabled_ui(!snapshots.snapshots.is_empty(), |ui| {
+            ui.checkbox(&mut snapshots.comparing, "Compare A/B")
+                .on_hover_text("Show snapshots A and B in the viewport instead of the render");
+            ui.radio_value(&mut snapshots.mode, CompareMode::Wipe, "Wipe")
+                .on_hover_text("Drag the split to wipe between A and B");
+            ui.radio_value(&mut snapshots.mode, CompareMode::Difference, "Difference")
+                .on_hover_text(
+                    "Absolute difference between A and B, brightened with the exposure of the display settings",
+                );
+        });
+    });
+
+    let mut restore = None;
+    let mut remove = None;
+    ScrollArea::horizontal().show(ui, |ui| {
+        ui.horizontal(|ui| {
+            if snapshots.snapshots.is_empty() {
+                ui.label("No snapshots are taken");
+            }
+            for snapshot in &mut snapshots.snapshots {
+                let id = snapshot.id;
+                ui.vertical(|ui| {
+                    let size = snapshot.size() * (THUMBNAIL_HEIGHT / snapshot.size().y.max(1.));
+                    let texture = snapshot.texture(ui, &rendered_image.display_settings);
+                    ui.image((texture.id(), size)).on_hover_text(format!(
+                        "{}x{}\n{:.0}% rendered\n{:.1} FPS",
+                        snapshot.width,
+                        snapshot.height,
+                        snapshot.progress * 100.,
+                        snapshot.fps
+                    ));
+                    ui.horizontal(|ui| {
+                        ui.label(&snapshot.name);
+                        if ui
+                            .selectable_label(snapshots.a == Some(id), "A")
+                            .on_hover_text("Compare as A")
+                            .clicked()
+                        {
+                            snapshots.a = Some(id);
+                        }
+                        if ui
+                            .selectable_label(snapshots.b == Some(id), "B")
+                            .on_hover_text("Compare as B")
+                            .clicked()
+                        {
+                            snapshots.b = Some(id);
+                        }
+                        if ui
+                            .small_button("Restore")
+                            .on_hover_text(
+                                "Bring back the scene yaml, variant and camera view of the snapshot",
+                            )
+                            .clicked()
+                        {
+                            restore = Some(snapshot.source.clone());
+                        }
+                        if ui.small_button("🗑").on_hover_text("Remove").clicked() {
+                            remove = Some(id);
+                        }
+                    });
+                });
+            }
+        });
+    });
+
+    if let Some(id) = remove {
+        snapshots.remove(id);
+    }
+    if let Some(source) = restore {
+        *scene_yaml = source.scene_yaml;
+        snapshots.comparing = false;
+        render_control.variant = source.variant;
+        render_control.camera = source.camera;
+        render_control.restored_view = source.view;
+        render_control.selected_object = None;
+        // Parses the scene again, instead of rendering the current scene model
+        render_control.camera_updated = false;
+        render_control.scene_updated = false;
+        render_control.render_requested = true;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn difference() {
+        assert_eq!(
+            vec![[0.5, 0., 2., 1.], [0., 0., 0., 1.]],
+            super::difference(
+                &[[1., 0.25, 0., 1.], [0.1, 0.2, 0.3, 1.]],
+                &[[0.5, 0.25, 2., 0.5], [0.1, 0.2, 0.3, 1.]]
+            )
+        );
+    }
+
+    #[test]
+    fn select_and_remove() {
+        let mut snapshots = Snapshots::default();
+        for _ in 0..3 {
+            let id = snapshots.next_id;
+            snapshots.next_id += 1;
+            snapshots.add(Snapshot {
+                id,
+                name: format!("Snapshot {id}"),
+                source: RenderSource::default(),
+                width: 1,
+                height: 1,
+                progress: 1.,
+                fps: 0.,
+                pixels: vec![[0.; 4]],
+                texture: None,
+            });
+        }
+        // The two latest snapshots are compared
+        assert_eq!(Some(2), snapshots.a);
+        assert_eq!(Some(3), snapshots.b);
+
+        snapshots.a = Some(1);
+        snapshots.comparing = true;
+        assert!(snapshots.is_comparing());
+
+        snapshots.remove(3);
+        assert_eq!(Some(1), snapshots.a);
+        assert_eq!(None, snapshots.b);
+        assert!(!snapshots.is_comparing());
+    }
+}
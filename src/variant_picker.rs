@@ -0,0 +1,33 @@
+use eframe::egui::{ComboBox, Ui};
+
+use crate::RenderControl;
+
+pub fn show(ui: &mut Ui, render_control: &mut RenderControl) {
+    let variants: Vec<String> = render_control
+        .scene
+        .as_ref()
+        .map(|s| s.variants.keys().cloned().collect())
+        .unwrap_or_default();
+
+    // The selection is kept visible even if the scene failed to parse, so that it can be cleared
+    if variants.is_empty() && render_control.variant.is_none() {
+        return;
+    }
+
+    let mut selected = render_control.variant.clone();
+    ComboBox::from_id_salt("variant-picker")
+        .selected_text(selected.as_deref().unwrap_or("No variant"))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut selected, None, "No variant");
+            for v in variants {
+                ui.selectable_value(&mut selected, Some(v.clone()), v);
+            }
+        })
+        .response
+        .on_hover_text("Select which of the scene variants to render");
+
+    if selected != render_control.variant {
+        render_control.variant = selected;
+        render_control.render_requested = true;
+    }
+}
@@ -3,12 +3,14 @@ use std::sync::Arc;
 use crate::model::DocumentationStructure;
 use eframe::egui;
 use eframe::egui::text::{LayoutJob, LayoutSection};
-use eframe::egui::{Context, Galley, Id, TextBuffer, TextEdit, TextFormat, Ui, Vec2};
+use eframe::egui::text_edit::TextEditOutput;
+use eframe::egui::{Align, Context, Galley, Id, TextBuffer, TextEdit, TextFormat, Ui, Vec2};
 use egui::util::cache::{ComputerMut, FrameCache};
 use once_cell::sync::Lazy;
 use regex::Regex;
 
 pub static YAML_EDITOR_ID: Lazy<Id> = Lazy::new(|| Id::from("yaml_editor"));
+static SCROLL_TO_CURSOR_ID: Lazy<Id> = Lazy::new(|| Id::from("yaml_editor_scroll_to_cursor"));
 static INDENTATION_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new("^[\\s-]*").unwrap());
 static TEMPLATE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new("\\{%.*%}").unwrap());
 static YAML_KEY_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new("^([\\s-]*)([\\w_]+):").unwrap());
@@ -34,6 +36,47 @@ fn cursor_char_offset(ctx: &Context) -> Option<usize> {
         .and_then(|state| state.cursor.char_range().map(|range| range.primary.index))
 }
 
+/// Moves the cursor to the first text on the given line. The editor is not focused, so that
+/// keys keep controlling the camera, and it scrolls to the cursor with [scroll_to_moved_cursor]
+/// once it has been shown
+pub fn move_cursor_to_line(text: &str, line: usize, ctx: &Context) {
+    let mut state = TextEdit::load_state(ctx, *YAML_EDITOR_ID).unwrap_or_default();
+
+    let line_start: usize = text.lines().take(line).map(|l| l.chars().count() + 1).sum();
+    let indentation = text
+        .lines()
+        .nth(line)
+        .map(|l| l.chars().take_while(|c| c.is_whitespace()).count())
+        .unwrap_or(0);
+
+    let cursor = egui::text::CCursor::new(line_start + indentation);
+    state
+        .cursor
+        .set_char_range(Some(egui::text::CCursorRange::one(cursor)));
+    state.store(ctx, *YAML_EDITOR_ID);
+    ctx.data_mut(|d| d.insert_temp(*SCROLL_TO_CURSOR_ID, true));
+}
+
+/// The editor only scrolls to the cursor when it is moved from within the editor
+pub fn scroll_to_moved_cursor(ui: &Ui, output: &TextEditOutput) {
+    let scroll = ui
+        .ctx()
+        .data_mut(|d| d.remove_temp::<bool>(*SCROLL_TO_CURSOR_ID))
+        .unwrap_or(false);
+
+    // The editor only reports the cursor while it has focus
+    let range = output
+        .cursor_range
+        .or_else(|| output.state.cursor.range(&output.galley));
+    if scroll && let Some(range) = range {
+        let rect = output
+            .galley
+            .pos_from_cursor(range.primary)
+            .translate(output.galley_pos.to_vec2());
+        ui.scroll_to_rect(rect, Some(Align::Center));
+    }
+}
+
 pub fn get_yaml_path(yaml: &dyn TextBuffer, ctx: &Context) -> Vec<String> {
     match cursor_char_offset(ctx) {
         None => vec![],